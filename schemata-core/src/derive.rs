@@ -0,0 +1,219 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{
+    parse_quote, Data, DataEnum, DeriveInput, Fields, Generics, Ident, LitStr, Type, WherePredicate,
+};
+
+/// Implement `Encode`, `Decode` and `KeyPart` for a struct or enum, by encoding each of its fields
+/// in turn, preceded by the index of the variant for an enum.
+pub fn key_part(input: DeriveInput) -> TokenStream {
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        ..
+    } = input;
+
+    let name = LitStr::new(&ident.to_string(), ident.span());
+
+    let (encode, decode, fixed_len, field_tys) = match &data {
+        Data::Struct(data) => {
+            let (pattern, bindings) = destructure(quote!(#ident), &data.fields);
+            let construct = construct(quote!(#ident), &data.fields);
+            let tys = field_tys(&data.fields);
+            (
+                quote! {
+                    let #pattern = self;
+                    #(::schemata::Encode::encode(#bindings, buf);)*
+                },
+                quote!(::core::result::Result::Ok(#construct)),
+                quote!(::schemata::__private::sum_fixed_len(&[
+                    #(<#tys as ::schemata::KeyPart>::FIXED_LEN),*
+                ])),
+                tys,
+            )
+        }
+        Data::Enum(data) => enum_impls(&ident, &name, data),
+        Data::Union(data) => abort!(
+            data.union_token,
+            "`KeyPart` can't be derived for unions";
+            help = "use a struct or an enum instead"
+        ),
+    };
+
+    let encode_generics = with_bounds(&generics, &field_tys, quote!(::schemata::Encode));
+    let (impl_generics, ty_generics, where_clause) = encode_generics.split_for_impl();
+    let encode_impl = quote! {
+        impl #impl_generics ::schemata::Encode for #ident #ty_generics #where_clause {
+            fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                #encode
+            }
+        }
+    };
+
+    let decode_generics = with_bounds(&generics, &field_tys, quote!(::schemata::Decode));
+    let (impl_generics, ty_generics, where_clause) = decode_generics.split_for_impl();
+    let decode_impl = quote! {
+        impl #impl_generics ::schemata::Decode for #ident #ty_generics #where_clause {
+            fn decode(input: &mut &[u8]) -> ::core::result::Result<Self, ::schemata::DecodeError> {
+                #decode
+            }
+        }
+    };
+
+    // The type is its own owned form, so it must be decodable and cloneable as well
+    let mut key_part_generics = with_bounds(
+        &generics,
+        &field_tys,
+        quote!(::schemata::KeyPart + ::schemata::Decode),
+    );
+    let (_, ty_generics, _) = generics.split_for_impl();
+    key_part_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#ident #ty_generics: ::core::clone::Clone + ::core::fmt::Debug));
+    let (impl_generics, ty_generics, where_clause) = key_part_generics.split_for_impl();
+    let key_part_impl = quote! {
+        impl #impl_generics ::schemata::KeyPart for #ident #ty_generics #where_clause {
+            type Owned = Self;
+            const FIXED_LEN: ::core::option::Option<usize> = #fixed_len;
+        }
+    };
+
+    quote! {
+        #encode_impl
+        #decode_impl
+        #key_part_impl
+    }
+}
+
+/// The bodies of `Encode::encode`, `Decode::decode` and `KeyPart::FIXED_LEN` for an enum, along
+/// with the types of all the fields of all its variants.
+fn enum_impls(
+    ident: &Ident,
+    name: &LitStr,
+    data: &DataEnum,
+) -> (TokenStream, TokenStream, TokenStream, Vec<Type>) {
+    // The index of the variant comes first, so that variants sort in the order they're declared,
+    // and it's as narrow as it can be while still fitting every variant
+    let tag_ty = if data.variants.len() <= 1 << u8::BITS {
+        quote!(u8)
+    } else {
+        quote!(u16)
+    };
+
+    let mut encode_arms = Vec::new();
+    let mut decode_arms = Vec::new();
+    let mut variant_lens = Vec::new();
+    let mut all_tys = Vec::new();
+    for (index, variant) in data.variants.iter().enumerate() {
+        let variant_ident = &variant.ident;
+        let path = quote!(#ident::#variant_ident);
+        let (pattern, bindings) = destructure(path.clone(), &variant.fields);
+        let construct = construct(path, &variant.fields);
+        let tys = field_tys(&variant.fields);
+
+        encode_arms.push(quote! {
+            #pattern => {
+                ::schemata::Encode::encode(&(#index as #tag_ty), buf);
+                #(::schemata::Encode::encode(#bindings, buf);)*
+            }
+        });
+        decode_arms.push(quote! {
+            #index => ::core::result::Result::Ok(#construct),
+        });
+        variant_lens.push(quote! {
+            ::schemata::__private::sum_fixed_len(&[
+                #(<#tys as ::schemata::KeyPart>::FIXED_LEN),*
+            ])
+        });
+        all_tys.extend(tys);
+    }
+
+    // An enum without variants can't be encoded, since there's no value to encode
+    let encode = if encode_arms.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            match self {
+                #(#encode_arms)*
+            }
+        }
+    };
+
+    let decode = quote! {
+        let index = <#tag_ty as ::schemata::Decode>::decode(input)?;
+        match index as usize {
+            #(#decode_arms)*
+            _ => ::core::result::Result::Err(::schemata::DecodeError::InvalidParam { ty: #name }),
+        }
+    };
+
+    // The tag is only worth counting if every variant has the same length after it
+    let fixed_len = quote! {
+        match ::schemata::__private::same_fixed_len(&[#(#variant_lens),*]) {
+            ::core::option::Option::Some(len) => {
+                ::core::option::Option::Some(::core::mem::size_of::<#tag_ty>() + len)
+            }
+            ::core::option::Option::None => ::core::option::Option::None,
+        }
+    };
+
+    (encode, decode, fixed_len, all_tys)
+}
+
+/// A pattern which binds each of the fields to a variable, along with those variables in order.
+fn destructure(path: TokenStream, fields: &Fields) -> (TokenStream, Vec<Ident>) {
+    match fields {
+        Fields::Named(fields) => {
+            let names: Vec<_> = fields
+                .named
+                .iter()
+                .map(|field| field.ident.clone().expect("named field has a name"))
+                .collect();
+            let bindings: Vec<_> = (0..names.len())
+                .map(|i| format_ident!("field_{i}"))
+                .collect();
+            (quote!(#path { #(#names: #bindings),* }), bindings)
+        }
+        Fields::Unnamed(fields) => {
+            let bindings: Vec<_> = (0..fields.unnamed.len())
+                .map(|i| format_ident!("field_{i}"))
+                .collect();
+            (quote!(#path(#(#bindings),*)), bindings)
+        }
+        Fields::Unit => (path, vec![]),
+    }
+}
+
+/// An expression which constructs the value by decoding each of its fields in turn.
+fn construct(path: TokenStream, fields: &Fields) -> TokenStream {
+    let decode = quote!(::schemata::Decode::decode(input)?);
+    match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#names: #decode),* })
+        }
+        Fields::Unnamed(fields) => {
+            let decodes = fields.unnamed.iter().map(|_| &decode);
+            quote!(#path(#(#decodes),*))
+        }
+        Fields::Unit => path,
+    }
+}
+
+fn field_tys(fields: &Fields) -> Vec<Type> {
+    fields.iter().map(|field| field.ty.clone()).collect()
+}
+
+/// The generics of the type, with every field's type required to implement `bound`.
+fn with_bounds(generics: &Generics, tys: &[Type], bound: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let where_clause = generics.make_where_clause();
+    for ty in tys {
+        let predicate: WherePredicate = parse_quote!(#ty: #bound);
+        where_clause.predicates.push(predicate);
+    }
+    generics
+}
@@ -1,16 +1,20 @@
 #![allow(non_snake_case)]
 
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{Ident, Type};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{
+    ext::IdentExt, parse_quote, spanned::Spanned, Ident, LitByteStr, Type, TypeGroup, TypeParen,
+    TypePath, Visibility,
+};
 
-use crate::ir::{Children, Ir, Kind, Names, Node, Settings};
+use crate::ir::{visitor_method_name, Children, Ir, Kind, Names, Node, Settings};
 
 impl ToTokens for Ir {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let context = Context {
             depth: 0,
             remaining_param_count: 0,
+            parent: None,
         };
         NodeInContextWithSettings {
             node: &self.root,
@@ -22,12 +26,13 @@ impl ToTokens for Ir {
 }
 
 #[derive(Clone, Copy)]
-pub struct Context {
+pub struct Context<'a> {
     depth: usize,
     remaining_param_count: usize,
+    parent: Option<&'a NodeInContextWithSettings<'a>>,
 }
 
-impl Context {
+impl Context<'_> {
     pub fn is_root(&self) -> bool {
         self.depth == 0
     }
@@ -39,7 +44,7 @@ impl Context {
 
 pub struct NodeInContextWithSettings<'a> {
     pub node: &'a Node,
-    pub context: Context,
+    pub context: Context<'a>,
     pub settings: &'a Settings,
 }
 
@@ -48,21 +53,327 @@ impl ToTokens for NodeInContextWithSettings<'_> {
         // These selectively only generate themselves when we're at the root
         self.root_schema_struct(tokens);
         self.root_schema_fns(tokens);
+        self.root_visitor(tokens);
 
-        // Generate all the structs for this module
+        // Generate all the structs for this module, and their trait implementations
         self.per_module_structs(tokens);
+        self.per_module_impls(tokens);
 
         // Generate all the child modules
         self.child_modules(tokens);
     }
 }
 
+impl<'a> NodeInContextWithSettings<'a> {
+    /// The context in which the children of this node are generated.
+    fn child_context(&self) -> Context<'_> {
+        let Self { node, context, .. } = self;
+
+        Context {
+            depth: context.depth + 1,
+            remaining_param_count: match node.header.kind {
+                Kind::Var { .. } => context.remaining_param_count - 1,
+                Kind::Static { param_count, .. } => param_count,
+            },
+            parent: Some(self),
+        }
+    }
+
+    /// Whether this node is the last node of a path segment, i.e. a static node without
+    /// parameters, or the last parameter of a static node with parameters.
+    fn ends_segment(&self) -> bool {
+        !self.context.is_root() && !self.child_context().has_remaining_params()
+    }
+
+    /// All the nodes from the root of the schema down to and including this node.
+    fn ancestry(&self) -> Vec<&NodeInContextWithSettings<'a>> {
+        let mut ancestry = vec![self];
+        let mut current = self;
+        while let Some(parent) = current.context.parent {
+            ancestry.push(parent);
+            current = parent;
+        }
+        ancestry.reverse();
+        ancestry
+    }
+
+    /// The nodes making up the path segment that ends at this node, starting with the named static
+    /// node and followed by one node for each of its parameters.
+    fn segment(&self) -> Vec<&NodeInContextWithSettings<'a>> {
+        let mut segment = vec![self];
+        let mut current = self;
+        while let Kind::Var { .. } = current.node.header.kind {
+            current = current
+                .context
+                .parent
+                .expect("parameter node always has a parent");
+            segment.push(current);
+        }
+        segment.reverse();
+        segment
+    }
+
+    /// The number of modules between the module of the named static node of this node's segment
+    /// and this node's own module, i.e. how many of the segment's parameters lead to this node.
+    fn depth_in_segment(&self) -> usize {
+        self.segment().len() - 1
+    }
+
+    /// The visibility of this node's segment, relative to the module of the segment containing it,
+    /// where it is declared: segments at the top level default to the visibility of the schema, and
+    /// segments beneath them to `pub`, since they are no more visible than their parents anyway.
+    fn segment_visibility(&self) -> Visibility {
+        let segment = self.segment()[0];
+        match &segment.node.header.vis {
+            Some(vis) => vis.clone(),
+            None => match segment.context.parent {
+                Some(parent) if parent.context.is_root() => self.settings.visibility.clone(),
+                _ => parse_quote!(pub),
+            },
+        }
+    }
+
+    /// The visibility of the types generated for this node: that of the schema, so that the types
+    /// of every node can name each other in their trait implementations, while the visibility of
+    /// their modules decides where they can actually be reached from.
+    fn item_visibility(&self) -> TokenStream {
+        visibility_beneath(&self.settings.visibility, self.context.depth)
+    }
+
+    /// The template for the keys at or beneath this node, like `baz/{x}/{y}/foo`, where each
+    /// parameter is written as its name in braces.
+    fn template(&self) -> String {
+        self.ancestry()
+            .iter()
+            .skip(1)
+            .map(|level| {
+                let header = &level.node.header;
+                header
+                    .static_name()
+                    .map(|name| name.value())
+                    .unwrap_or_else(|| {
+                        let name = header.mod_name.as_ref().expect("parameter has a name");
+                        format!("{{{}}}", name.unraw())
+                    })
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// A line of documentation showing the template for the key of this node if it is a leaf, or
+    /// the prefix shared by all the keys beneath it otherwise.
+    fn template_doc(&self) -> TokenStream {
+        let template = self.template();
+        let doc = if self.node.is_leaf() {
+            format!(" Key: `{template}`")
+        } else {
+            format!(" Prefix: `{template}/`")
+        };
+        quote!(#[doc = #doc])
+    }
+
+    /// The documentation for the items generated for this node: whatever was written in the schema,
+    /// followed by the template for its keys.
+    fn docs(&self) -> TokenStream {
+        let docs = &self.node.header.docs;
+
+        // The root's template is empty, so there's nothing to show
+        if self.context.is_root() {
+            return quote!(#(#[doc = #docs])*);
+        }
+
+        let template = self.template_doc();
+        if docs.is_empty() {
+            template
+        } else {
+            quote! {
+                #(#[doc = #docs])*
+                #[doc = ""]
+                #template
+            }
+        }
+    }
+}
+
+/// A relative path to the module `levels` levels above the current one.
+fn supers(levels: usize) -> TokenStream {
+    (0..levels).map(|_| quote!(super::)).collect()
+}
+
+/// The match arm for the variant generated by [`uninhabited_if_empty`], if any.
+fn uninhabited_arm_if_empty(enum_name: &Ident, variants: &[&Ident]) -> TokenStream {
+    if variants.is_empty() {
+        quote!(#enum_name::__(never, _) => match *never {},)
+    } else {
+        quote!()
+    }
+}
+
+/// The scrutinee to use when matching on `&self` for an owned enum: owned enums don't get an
+/// uninhabited variant, so when they have no variants at all we need to match on `*self` instead.
+fn owned_scrutinee(variants: &[&Ident]) -> TokenStream {
+    if variants.is_empty() {
+        quote!(*self)
+    } else {
+        quote!(self)
+    }
+}
+
+/// A visibility written relative to some module, rewritten to mean the same thing for an item in a
+/// module `levels` levels beneath it.
+fn visibility_beneath(vis: &Visibility, levels: usize) -> TokenStream {
+    let supers = || (0..levels).map(|_| quote!(super));
+
+    if levels == 0 {
+        return quote!(#vis);
+    }
+
+    match vis {
+        Visibility::Public(_) | Visibility::Crate(_) => quote!(#vis),
+        Visibility::Inherited => {
+            let supers = supers();
+            quote!(pub(in #(#supers)::*))
+        }
+        Visibility::Restricted(restricted) => {
+            let path = &restricted.path;
+            let mut segments = path.segments.iter().peekable();
+            let first = segments.peek().map(|segment| segment.ident.to_string());
+            match first.as_deref() {
+                // Paths relative to the module need to be relative to the one further up instead
+                Some("self") if path.leading_colon.is_none() => {
+                    let supers = supers();
+                    let rest = segments.skip(1);
+                    quote!(pub(in #(#supers)::* #(::#rest)*))
+                }
+                Some("super") if path.leading_colon.is_none() => {
+                    let supers = supers();
+                    quote!(pub(in #(#supers::)* #path))
+                }
+                _ => quote!(#vis),
+            }
+        }
+    }
+}
+
+/// The names of the modules from a node down to the end of its segment.
+fn segment_path(node: &Node) -> Vec<&Ident> {
+    std::iter::once(node)
+        .chain(node.segment_params())
+        .map(|node| {
+            node.header
+                .mod_name
+                .as_ref()
+                .expect("segment always has a module name")
+        })
+        .collect()
+}
+
+/// The type in borrowed paths of a parameter of the given type: the type itself if it is passed by
+/// value, or else a reference to it.
+fn borrowed_ty(ty: &Type, by_value: bool) -> TokenStream {
+    if by_value {
+        quote!(#ty)
+    } else {
+        quote!(&'a #ty)
+    }
+}
+
+/// The type in owned paths of a parameter of the given type, which differs from the type itself
+/// for unsized types like `str`.
+fn owned_ty(ty: &Type) -> TokenStream {
+    // Sized types are their own owned form, so they are written as they are: a projection would
+    // fail to resolve everywhere the owned parameters are used if the type isn't a `KeyPart`, where
+    // the type itself only fails where it's checked to be one
+    if is_unsized(ty) {
+        quote_spanned!(ty.span()=> <#ty as ::schemata::KeyPart>::Owned)
+    } else {
+        quote!(#ty)
+    }
+}
+
+/// Whether a parameter type is written as an unsized type, like `str` or `[u8]`.
+fn is_unsized(ty: &Type) -> bool {
+    match ty {
+        Type::Slice(_) | Type::TraitObject(_) => true,
+        Type::Path(TypePath { qself: None, path }) => path.is_ident("str"),
+        Type::Paren(TypeParen { elem, .. }) | Type::Group(TypeGroup { elem, .. }) => {
+            is_unsized(elem)
+        }
+        _ => false,
+    }
+}
+
+/// The name of the subcommand for a child module, when using the `clap` extension.
+fn subcommand_name(mod_name: &Ident) -> String {
+    mod_name.unraw().to_string().replace('_', "-")
+}
+
+/// A variant to use the lifetime of a borrowed enum which would otherwise have no variants, and
+/// hence not use its lifetime parameter.
+fn uninhabited_if_empty(variants: &[&Ident]) -> TokenStream {
+    if variants.is_empty() {
+        quote! {
+            #[doc(hidden)]
+            __(::core::convert::Infallible, ::core::marker::PhantomData<&'a ()>),
+        }
+    } else {
+        quote!()
+    }
+}
+
 impl NodeInContextWithSettings<'_> {
+    /// The module names of all the children of this node.
+    fn child_mod_names(&self) -> Vec<&Ident> {
+        match &self.node.children {
+            Ok(Children::Below(children)) => children
+                .iter()
+                .map(|child| {
+                    child
+                        .header
+                        .mod_name
+                        .as_ref()
+                        .expect("child module has a module name")
+                })
+                .collect(),
+            Ok(Children::Leaf(_)) | Err(_) => vec![],
+        }
+    }
+
+    /// The module names of all the children of this node which are not leaves, i.e. those which
+    /// have prefixes of their own.
+    fn internal_child_mod_names(&self) -> Vec<&Ident> {
+        match &self.node.children {
+            Ok(Children::Below(children)) => children
+                .iter()
+                .filter(|child| !child.is_leaf())
+                .map(|child| {
+                    child
+                        .header
+                        .mod_name
+                        .as_ref()
+                        .expect("child module has a module name")
+                })
+                .collect(),
+            Ok(Children::Leaf(_)) | Err(_) => vec![],
+        }
+    }
+
+    /// Whether this node has exactly one child, and that child is a parameter, i.e. this node is a
+    /// parameterized segment or one of its parameters other than the last.
+    fn only_child_is_param(&self) -> bool {
+        match &self.node.children {
+            Ok(Children::Below(children)) => {
+                matches!(children.as_slice(), [child] if matches!(child.header.kind, Kind::Var { .. }))
+            }
+            Ok(Children::Leaf(_)) | Err(_) => false,
+        }
+    }
+
     fn root_schema_struct(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
+
         let Self {
-            node,
-            context,
-            settings,
+            context, settings, ..
         } = self;
 
         // Only generate the schema struct for the root of the schema
@@ -74,16 +385,33 @@ impl NodeInContextWithSettings<'_> {
             Schema,
             Path,
             OwnedPath,
+            Key,
+            OwnedKey,
+            Prefix,
+            OwnedPrefix,
             Params,
             OwnedParams,
             ..
         } = &settings.names;
 
+        // The code derived by `clap` refers to it by a relative path, so it needs to be in scope
+        // here and (through their glob imports) in all the child modules; importing it by a glob
+        // here too means it won't conflict with any `clap` the user imports themselves
+        if settings.extensions.clap {
+            tokens.extend(quote! {
+                #[allow(unused_imports)]
+                use ::schemata::__private::clap_scope::*;
+            });
+        }
+
+        let docs = self.docs();
+
         tokens.extend(quote! {
+            #docs
             #[derive(
                 ::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq,
             )]
-            pub struct #Schema;
+            #vis struct #Schema;
 
             impl #Schema {
                 /// Get the root path of this schema.
@@ -110,6 +438,23 @@ impl NodeInContextWithSettings<'_> {
                     #Schema
                 }
             }
+
+            impl ::schemata::Schema for #Schema {
+                type Path<'a> = #Path<'a>;
+                type OwnedPath = #OwnedPath;
+                type Key<'a> = #Key<'a>;
+                type OwnedKey = #OwnedKey;
+                type Prefix<'a> = #Prefix<'a>;
+                type OwnedPrefix = #OwnedPrefix;
+
+                fn root<'a>() -> #Path<'a> {
+                    #Schema::root()
+                }
+
+                fn owned_root() -> #OwnedPath {
+                    #Schema::owned_root()
+                }
+            }
         });
     }
 
@@ -125,7 +470,12 @@ impl NodeInContextWithSettings<'_> {
             return;
         }
 
-        let Names { Schema, Path, .. } = &settings.names;
+        let Names {
+            Schema,
+            Path,
+            OwnedPath,
+            ..
+        } = &settings.names;
 
         if let Ok(children) = &node.children {
             match children {
@@ -137,11 +487,59 @@ impl NodeInContextWithSettings<'_> {
                             .as_ref()
                             .expect("child module always has a name");
 
+                        // Duplicates are already an error, so there's no need to generate anything
+                        if child.children.is_err() {
+                            continue;
+                        }
+
+                        // The functions take all the parameters of the segment, and return the
+                        // path to the end of it
+                        let params: Vec<_> = child
+                            .segment_params()
+                            .into_iter()
+                            .map(|param| {
+                                let field = param
+                                    .header
+                                    .mod_name
+                                    .as_ref()
+                                    .expect("parameter has a name");
+                                let Kind::Var { ty, by_value } = &param.header.kind else {
+                                    unreachable!("parameter is always a var node")
+                                };
+                                (field, ty, *by_value)
+                            })
+                            .collect();
+                        let fields: Vec<_> = params.iter().map(|(field, ..)| field).collect();
+                        let tys: Vec<_> = params
+                            .iter()
+                            .map(|(_, ty, by_value)| borrowed_ty(ty, *by_value))
+                            .collect();
+                        let owned_tys: Vec<_> =
+                            params.iter().map(|(_, ty, _)| owned_ty(ty)).collect();
+                        let path = segment_path(child);
+                        let owned_name = format_ident!("owned_{}", name.unraw());
+
+                        let vis = child.header.vis.as_ref().unwrap_or(&settings.visibility);
+                        let doc = format!(" Get the path to the `{name}` segment of the schema.");
+                        let owned_doc = format!(
+                            " Get the path to the `{name}` segment of the schema, as an owned path."
+                        );
+
+                        tokens.extend(quote! {
+                            #[doc = #doc]
+                            #vis fn #name<'a>(#(#fields: #tys),*) -> #(#path::)* #Path<'a> {
+                                #Schema::root().#name(#(#fields),*)
+                            }
+                        });
+
                         tokens.extend(quote! {
-                            pub fn #name<'a>() -> #name::#Path<'a> {
-                                #Schema::root().#name()
+                            impl #Schema {
+                                #[doc = #owned_doc]
+                                #vis fn #owned_name(#(#fields: #owned_tys),*) -> #(#path::)* #OwnedPath {
+                                    #Schema::owned_root().#name(#(#fields),*)
+                                }
                             }
-                        })
+                        });
                     }
                 }
                 Children::Leaf(_) => unreachable!("root of schema can't be a bare type"),
@@ -160,13 +558,47 @@ impl NodeInContextWithSettings<'_> {
         self.prefix_structs(tokens);
         self.sub_prefix_structs(tokens);
         self.sub_key_structs(tokens);
+        self.leaf_enum(tokens);
+    }
+
+    fn per_module_impls(&self, tokens: &mut TokenStream) {
+        self.params_encode_impls(tokens);
+        self.params_eq_impls(tokens);
+        self.params_decode_impls(tokens);
+        self.key_encode_impls(tokens);
+        self.key_decode_impls(tokens);
+
+        // Only generated when not a terminal leaf
+        self.prefix_encode_impls(tokens);
+        self.display_impls(tokens);
+        self.order_impls(tokens);
+        self.leaf_from_impls(tokens);
+
+        // Only generated when a terminal leaf
+        self.key_trait_impls(tokens);
+        self.key_leaf_decode_impls(tokens);
+
+        self.path_trait_impls(tokens);
+        self.params_trait_impls(tokens);
+        self.accessor_impls(tokens);
+        self.owned_conversion_impls(tokens);
+
+        // Only generated at the root
+        self.prefix_trait_impls(tokens);
+
+        // Only generated with the `clap` extension, when the only child is a parameter
+        self.owned_sub_key_args_impls(tokens);
+
+        // Only generated with the `cli` extension, when not a terminal leaf
+        self.owned_key_text_impls(tokens);
+        self.owned_prefix_args_impls(tokens);
     }
 
     fn path_structs(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
+
         let Self {
-            node,
-            context,
-            settings,
+            context, settings, ..
         } = self;
 
         let Names {
@@ -181,62 +613,163 @@ impl NodeInContextWithSettings<'_> {
         let (parent, owned_parent) = if context.is_root() {
             (quote!(#Schema), quote!(#Schema))
         } else {
-            (quote!(super::#Path), quote!(super::#OwnedPath))
+            (quote!(super::#Path<'a>), quote!(super::#OwnedPath))
         };
 
+        let docs = self.docs();
+
         tokens.extend(quote! {
+            #docs
             #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            pub struct #Path<'a> {
+            #vis struct #Path<'a> {
                 params: #Params<'a>,
                 parent: #parent,
             }
 
+            #docs
             #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            pub struct #OwnedPath {
+            #vis struct #OwnedPath {
                 params: #OwnedParams,
                 parent: #owned_parent,
             }
         });
-    }
 
-    fn prefix_structs(&self, tokens: &mut TokenStream) {
-        let Self {
-            node,
-            context,
-            settings,
-        } = self;
+        self.path_navigation_fn(tokens);
+        self.path_key_fns(tokens);
+        self.path_prefix_fns(tokens);
+    }
 
-        // Don't generate these for leaves of the schema
-        if node.is_leaf() {
-            return;
-        }
+    fn path_navigation_fn(&self, tokens: &mut TokenStream) {
+        let Self { settings, .. } = self;
 
         let Names {
+            Path,
+            OwnedPath,
             Params,
             OwnedParams,
-            Prefix,
-            OwnedPrefix,
-            SubPrefix,
-            OwnedSubPrefix,
             ..
         } = &settings.names;
 
+        // The navigation function for a segment takes all of its parameters at once, so it is
+        // generated only once we reach the end of the segment, where all of them are known
+        if !self.ends_segment() {
+            return;
+        }
+
+        let segment = self.segment();
+        let name = segment[0]
+            .node
+            .header
+            .mod_name
+            .as_ref()
+            .expect("segment always has a module name");
+
+        // Build up the path from the parent of the segment down to this node, collecting the
+        // arguments of the function along the way
+        let mut args = Vec::new();
+        let mut owned_args = Vec::new();
+        let mut path = quote!(self);
+        let mut owned_path = quote!(self);
+        for (i, level) in segment.iter().enumerate() {
+            let up = supers(segment.len() - 1 - i);
+            let (params, owned_params) = match &level.node.header.kind {
+                Kind::Var { ty, by_value } => {
+                    let field = level
+                        .node
+                        .header
+                        .mod_name
+                        .as_ref()
+                        .expect("mod name is specified when params are present");
+                    let borrowed_ty = borrowed_ty(ty, *by_value);
+                    args.push(quote!(#field: #borrowed_ty));
+                    let owned_ty = owned_ty(ty);
+                    owned_args.push(quote!(#field: #owned_ty));
+                    let phantom = by_value.then(|| quote!(__: ::core::marker::PhantomData,));
+                    (
+                        quote!(#up #Params { #field, #phantom }),
+                        quote!(#up #OwnedParams { #field }),
+                    )
+                }
+                Kind::Static { .. } => (
+                    quote!(#up #Params { __: ::core::marker::PhantomData }),
+                    quote!(#up #OwnedParams {}),
+                ),
+            };
+            path = quote! {
+                #up #Path {
+                    params: #params,
+                    parent: #path,
+                }
+            };
+            owned_path = quote! {
+                #up #OwnedPath {
+                    params: #owned_params,
+                    parent: #owned_path,
+                }
+            };
+        }
+
+        // Document the function with the documentation of the segment, followed by that of each of
+        // its parameters, and finally the template for the keys it leads to
+        let docs = &segment[0].node.header.docs;
+        let docs = if docs.is_empty() {
+            let doc = format!(" Get the path to the `{name}` segment beneath this path.");
+            quote!(#[doc = #doc])
+        } else {
+            quote!(#(#[doc = #docs])*)
+        };
+        let param_docs: Vec<_> = segment[1..]
+            .iter()
+            .flat_map(|level| {
+                let header = &level.node.header;
+                let field = header.mod_name.as_ref().expect("parameter has a name");
+                header.docs.iter().enumerate().map(move |(i, doc)| {
+                    let doc = if i == 0 {
+                        format!(" - `{field}`:{}", doc.value())
+                    } else {
+                        format!("  {}", doc.value())
+                    };
+                    quote!(#[doc = #doc])
+                })
+            })
+            .collect();
+        let template = self.template_doc();
+
+        let segment_parent = supers(segment.len());
+
+        // The function is as visible as the segment, whose visibility is written relative to the
+        // module of the segment containing it, some way above this one
+        let parent_depth = segment[0]
+            .context
+            .parent
+            .expect("segment always has a parent")
+            .depth_in_segment();
+        let vis = visibility_beneath(&self.segment_visibility(), parent_depth + segment.len());
+
         tokens.extend(quote! {
-            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            pub struct #Prefix<'a> {
-                params: #Params<'a>,
-                child: ::core::option::Option<#SubPrefix<'a>>,
+            impl<'a> #segment_parent #Path<'a> {
+                #docs
+                #[doc = ""]
+                #(#param_docs)*
+                #template
+                #vis fn #name(self, #(#args),*) -> #Path<'a> {
+                    #path
+                }
             }
 
-            #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            pub struct #OwnedPrefix {
-                params: #OwnedParams,
-                child: ::core::option::Option<#OwnedSubPrefix>,
+            impl #segment_parent #OwnedPath {
+                #docs
+                #[doc = ""]
+                #(#param_docs)*
+                #template
+                #vis fn #name(self, #(#owned_args),*) -> #OwnedPath {
+                    #owned_path
+                }
             }
         });
     }
 
-    fn key_structs(&self, tokens: &mut TokenStream) {
+    fn path_key_fns(&self, tokens: &mut TokenStream) {
         let Self {
             node,
             context,
@@ -244,8 +777,8 @@ impl NodeInContextWithSettings<'_> {
         } = self;
 
         let Names {
-            Params,
-            OwnedParams,
+            Path,
+            OwnedPath,
             Key,
             OwnedKey,
             SubKey,
@@ -253,112 +786,381 @@ impl NodeInContextWithSettings<'_> {
             ..
         } = &settings.names;
 
-        let (derive_clap_args, group_skip, clap_flatten, clap_subcommand) =
-            if settings.extensions.clap {
-                (
-                    quote!(derive(::clap::Args)),
-                    quote!(#[group(skip)]),
-                    quote!(#[clap(flatten)]),
-                    quote!(#[clap(subcommand)]),
-                )
-            } else {
-                (quote!(), quote!(), quote!(), quote!())
-            };
+        // At a leaf, the key is built by handing our parameters to our parent, which wraps them up
+        // in its own key and passes that on to its parent, and so on up to the root
+        if node.is_leaf() {
+            let name = node
+                .header
+                .mod_name
+                .as_ref()
+                .expect("leaf always has a module name");
+
+            tokens.extend(quote! {
+                impl<'a> #Path<'a> {
+                    /// Get the key for this path.
+                    pub fn key(self) -> #Key<'a> {
+                        #Key {
+                            key: self.parent.key_with_child(super::#SubKey::#name(self.params)),
+                        }
+                    }
+                }
+
+                impl #OwnedPath {
+                    /// Convert this path into the key for this path.
+                    pub fn into_key(self) -> #OwnedKey {
+                        #OwnedKey {
+                            key: self.parent.into_key_with_child(super::#OwnedSubKey::#name(self.params)),
+                        }
+                    }
+                }
+            });
+            return;
+        }
+
+        let root = supers(context.depth);
+        let (wrap, owned_wrap) = if context.is_root() {
+            (quote!(key), quote!(key))
+        } else {
+            let name = node
+                .header
+                .mod_name
+                .as_ref()
+                .expect("non-root node always has a module name");
+            (
+                quote!(self.parent.key_with_child(super::#SubKey::#name(key))),
+                quote!(self.parent.into_key_with_child(super::#OwnedSubKey::#name(key))),
+            )
+        };
 
         tokens.extend(quote! {
-            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            pub struct #Key<'a> {
-                params: #Params<'a>,
-                child: #SubKey<'a>,
+            impl<'a> #Path<'a> {
+                fn key_with_child(self, child: #SubKey<'a>) -> #root #Key<'a> {
+                    let key = #Key {
+                        params: self.params,
+                        child,
+                    };
+                    #wrap
+                }
             }
 
-            #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            #derive_clap_args
-            #group_skip
-            pub struct #OwnedKey {
-                #clap_flatten
-                params: #OwnedParams,
-                #clap_subcommand
-                child: #OwnedSubKey,
+            impl #OwnedPath {
+                fn into_key_with_child(self, child: #OwnedSubKey) -> #root #OwnedKey {
+                    let key = #OwnedKey {
+                        params: self.params,
+                        child,
+                    };
+                    #owned_wrap
+                }
             }
         });
     }
 
-    fn params_structs(&self, tokens: &mut TokenStream) {
+    fn path_prefix_fns(&self, tokens: &mut TokenStream) {
         let Self {
             node,
             context,
             settings,
         } = self;
 
+        // Leaves don't have prefixes
+        if node.is_leaf() {
+            return;
+        }
+
         let Names {
-            Params,
-            OwnedParams,
+            Path,
+            OwnedPath,
+            Prefix,
+            OwnedPrefix,
+            SubPrefix,
+            OwnedSubPrefix,
             ..
         } = &settings.names;
 
-        let (derive_clap_args, group_skip, clap_long) = if settings.extensions.clap {
-            (
-                quote!(derive(::clap::Args)),
-                quote!(#[group(skip)]),
-                quote!(#[clap(long)]),
-            )
+        // Just like keys, prefixes are built by wrapping them up in each parent's prefix in turn
+        let root = supers(context.depth);
+        let (wrap, owned_wrap) = if context.is_root() {
+            (quote!(prefix), quote!(prefix))
         } else {
-            (quote!(), quote!(), quote!())
-        };
-
-        // If there is a parameter at this level, put it in `Params`
-        let one_param_structs = |ty| {
-            let field = node
+            let name = node
                 .header
                 .mod_name
                 .as_ref()
-                .expect("mod name is specified when params are present");
+                .expect("non-root node always has a module name");
+            (
+                quote! {
+                    self.parent.prefix_with_child(
+                        ::core::option::Option::Some(super::#SubPrefix::#name(prefix))
+                    )
+                },
+                quote! {
+                    self.parent.into_prefix_with_child(
+                        ::core::option::Option::Some(super::#OwnedSubPrefix::#name(prefix))
+                    )
+                },
+            )
+        };
 
-            quote! {
-                #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-                #[allow(non_snake_case)]
-                pub struct #Params<'a> {
-                    pub #field: &'a #ty,
+        tokens.extend(quote! {
+            impl<'a> #Path<'a> {
+                /// Get the prefix shared by all keys beneath this path.
+                pub fn prefix(self) -> #root #Prefix<'a> {
+                    self.prefix_with_child(::core::option::Option::None)
                 }
 
-                #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-                #[allow(non_snake_case)]
-                #derive_clap_args
-                #group_skip
-                pub struct #OwnedParams {
-                    #clap_long
-                    pub #field: #ty,
+                fn prefix_with_child(
+                    self,
+                    child: ::core::option::Option<#SubPrefix<'a>>,
+                ) -> #root #Prefix<'a> {
+                    let prefix = #Prefix {
+                        params: self.params,
+                        child,
+                    };
+                    #wrap
                 }
             }
-        };
 
-        // If there are no parameters at this level, make `Params` empty (except for the lifetime)
-        let zero_param_structs = || {
-            quote! {
-                #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-                pub struct #Params<'a> {
-                    __: ::core::marker::PhantomData<&'a ()>,
+            impl #OwnedPath {
+                /// Convert this path into the prefix shared by all keys beneath this path.
+                pub fn into_prefix(self) -> #root #OwnedPrefix {
+                    self.into_prefix_with_child(::core::option::Option::None)
                 }
 
-                #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-                pub struct #OwnedParams {}
+                fn into_prefix_with_child(
+                    self,
+                    child: ::core::option::Option<#OwnedSubPrefix>,
+                ) -> #root #OwnedPrefix {
+                    let prefix = #OwnedPrefix {
+                        params: self.params,
+                        child,
+                    };
+                    #owned_wrap
+                }
             }
-        };
-
-        tokens.extend(match &node.header.kind {
-            Kind::Var(ty) => one_param_structs(ty),
-            Kind::Static { .. } => zero_param_structs(),
         });
     }
 
-    fn sub_prefix_structs(&self, tokens: &mut TokenStream) {
+    fn prefix_structs(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
+
+        let Self { node, settings, .. } = self;
+
+        // Don't generate these for leaves of the schema
+        if node.is_leaf() {
+            return;
+        }
+
+        let Names {
+            Params,
+            OwnedParams,
+            Prefix,
+            OwnedPrefix,
+            SubPrefix,
+            OwnedSubPrefix,
+            ..
+        } = &settings.names;
+
+        let docs = self.docs();
+
+        tokens.extend(quote! {
+            #docs
+            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #vis struct #Prefix<'a> {
+                params: #Params<'a>,
+                child: ::core::option::Option<#SubPrefix<'a>>,
+            }
+
+            #docs
+            #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #vis struct #OwnedPrefix {
+                params: #OwnedParams,
+                child: ::core::option::Option<#OwnedSubPrefix>,
+            }
+        });
+    }
+
+    fn key_structs(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
+
         let Self {
             node,
             context,
             settings,
         } = self;
 
+        let Names {
+            Params,
+            OwnedParams,
+            Key,
+            OwnedKey,
+            SubKey,
+            OwnedSubKey,
+            ..
+        } = &settings.names;
+
+        // When the only child is a parameter, its arguments are flattened into ours rather than
+        // being given a subcommand of their own (see `owned_sub_key_args_impls`)
+        let (derive_clap_args, group_skip, clap_flatten, clap_child) = if settings.extensions.clap {
+            (
+                quote!(#[derive(::schemata::clap::Args)]),
+                quote!(#[group(skip)]),
+                quote!(#[clap(flatten)]),
+                if self.only_child_is_param() {
+                    quote!(#[clap(flatten)])
+                } else {
+                    quote!(#[clap(subcommand)])
+                },
+            )
+        } else {
+            (quote!(), quote!(), quote!(), quote!())
+        };
+
+        let docs = self.docs();
+
+        // The key for a leaf is the complete key from the root of the schema down to that leaf,
+        // since a leaf's own parameters are already stored directly in its parent's `SubKey`
+        if node.is_leaf() {
+            let root = supers(context.depth);
+
+            tokens.extend(quote! {
+                #docs
+                #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+                #vis struct #Key<'a> {
+                    key: #root #Key<'a>,
+                }
+
+                #docs
+                #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+                #vis struct #OwnedKey {
+                    key: #root #OwnedKey,
+                }
+            });
+            return;
+        }
+
+        tokens.extend(quote! {
+            #docs
+            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #vis struct #Key<'a> {
+                params: #Params<'a>,
+                child: #SubKey<'a>,
+            }
+
+            #docs
+            #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #derive_clap_args
+            #group_skip
+            #vis struct #OwnedKey {
+                #clap_flatten
+                params: #OwnedParams,
+                #clap_child
+                child: #OwnedSubKey,
+            }
+        });
+    }
+
+    fn params_structs(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
+
+        let Self { node, settings, .. } = self;
+
+        let Names {
+            Params,
+            OwnedParams,
+            ..
+        } = &settings.names;
+
+        let (derive_clap_args, group_skip, clap_long) = if settings.extensions.clap {
+            (
+                quote!(#[derive(::schemata::clap::Args)]),
+                quote!(#[group(skip)]),
+                quote!(#[clap(long, allow_negative_numbers = true)]),
+            )
+        } else {
+            (quote!(), quote!(), quote!())
+        };
+
+        // Equality is not derived here: it is defined by encoding in `params_eq_impls`, so that
+        // parameter types like `f32` which are not `Eq` can still be used
+
+        // If there is a parameter at this level, put it in `Params`
+        let one_param_structs = |ty: &Type, by_value: bool| {
+            let field = node
+                .header
+                .mod_name
+                .as_ref()
+                .expect("mod name is specified when params are present");
+
+            // Check that the type can be a parameter (and is its own owned form, if it's sized, and
+            // `Copy`, if it's held by value) up front, so that if not, the error points at where
+            // it's written in the schema rather than somewhere in generated code
+            let assert_key_part = if is_unsized(ty) {
+                quote!(assert_key_part)
+            } else {
+                quote!(assert_sized_key_part)
+            };
+            let assert_copy = by_value.then(
+                || quote_spanned!(ty.span()=> let _ = ::schemata::__private::assert_copy::<#ty>;),
+            );
+            let assert_key_part = quote_spanned! {ty.span()=>
+                const _: () = {
+                    let _ = ::schemata::__private::#assert_key_part::<#ty>;
+                    #assert_copy
+                };
+            };
+
+            // A parameter held by value doesn't use the lifetime, so a marker uses it instead
+            let borrowed_ty = borrowed_ty(ty, by_value);
+            let phantom = by_value.then(|| quote!(__: ::core::marker::PhantomData<&'a ()>,));
+            let owned_ty = owned_ty(ty);
+
+            quote! {
+                #assert_key_part
+
+                #[derive(::core::clone::Clone, ::core::marker::Copy)]
+                #[allow(non_snake_case)]
+                #vis struct #Params<'a> {
+                    pub #field: #borrowed_ty,
+                    #phantom
+                }
+
+                #[derive(::core::clone::Clone)]
+                #[allow(non_snake_case)]
+                #derive_clap_args
+                #group_skip
+                #vis struct #OwnedParams {
+                    #clap_long
+                    pub #field: #owned_ty,
+                }
+            }
+        };
+
+        // If there are no parameters at this level, make `Params` empty (except for the lifetime)
+        let zero_param_structs = || {
+            quote! {
+                #[derive(::core::clone::Clone, ::core::marker::Copy)]
+                #vis struct #Params<'a> {
+                    __: ::core::marker::PhantomData<&'a ()>,
+                }
+
+                #[derive(::core::clone::Clone)]
+                #derive_clap_args
+                #group_skip
+                #vis struct #OwnedParams {}
+            }
+        };
+
+        tokens.extend(match &node.header.kind {
+            Kind::Var { ty, by_value } => one_param_structs(ty, *by_value),
+            Kind::Static { .. } => zero_param_structs(),
+        });
+    }
+
+    fn sub_prefix_structs(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
+
+        let Self { node, settings, .. } = self;
+
         let Names {
             Prefix,
             OwnedPrefix,
@@ -367,12 +1169,6 @@ impl NodeInContextWithSettings<'_> {
             ..
         } = &settings.names;
 
-        let derive_clap_subcommand = if settings.extensions.clap {
-            quote!(derive(::clap::Subcommand))
-        } else {
-            quote!()
-        };
-
         let no_children = &vec![];
         let subkey: Vec<&Ident> = match &node.children {
             // If we're a leaf, we shouldn't generate subprefix structs at all
@@ -381,6 +1177,8 @@ impl NodeInContextWithSettings<'_> {
             Err(_) => no_children,
         }
         .iter()
+        // Leaves don't have prefixes of their own, since their prefix would be their key
+        .filter(|child| !child.is_leaf())
         .map(|child| {
             child
                 .header
@@ -390,70 +1188,117 @@ impl NodeInContextWithSettings<'_> {
         })
         .collect();
 
+        let phantom = uninhabited_if_empty(&subkey);
+
         tokens.extend(quote! {
             #[allow(non_camel_case_types)]
             #[non_exhaustive]
             #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            enum #SubPrefix<'a> {
-                #(#subkey(#subkey::#Prefix<'a>)),*
+            #vis enum #SubPrefix<'a> {
+                #(#subkey(#subkey::#Prefix<'a>),)*
+                #phantom
             }
         });
 
         tokens.extend(quote! {
             #[allow(non_camel_case_types)]
             #[non_exhaustive]
-            #derive_clap_subcommand
             #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            enum #OwnedSubPrefix {
+            #vis enum #OwnedSubPrefix {
                 #(#subkey(#subkey::#OwnedPrefix)),*
             }
         });
     }
 
     fn sub_key_structs(&self, tokens: &mut TokenStream) {
-        let Self {
-            node,
-            context,
-            settings,
-        } = self;
+        let vis = self.item_visibility();
+
+        let Self { node, settings, .. } = self;
 
         let Names {
             Key,
             OwnedKey,
+            Params,
+            OwnedParams,
             SubKey,
             OwnedSubKey,
             ..
         } = &settings.names;
 
-        let derive_clap_subcommand = if settings.extensions.clap {
-            quote!(derive(::clap::Subcommand))
+        let derives_clap_subcommand = settings.extensions.clap && !self.only_child_is_param();
+        let derive_clap_subcommand = if derives_clap_subcommand {
+            quote!(#[derive(::schemata::clap::Subcommand)])
         } else {
             quote!()
         };
 
         let no_children = &vec![];
-        let subkey: Vec<&Ident> = match &node.children {
+        let children = match &node.children {
             // If we're a leaf, we shouldn't generate subkey structs at all
             Ok(Children::Leaf(_)) => return,
             Ok(Children::Below(children)) => children,
             Err(_) => no_children,
-        }
-        .iter()
-        .map(|child| {
-            child
-                .header
-                .mod_name
-                .as_ref()
-                .expect("child module has a module name")
-        })
-        .collect();
+        };
+
+        let subkey: Vec<&Ident> = children
+            .iter()
+            .map(|child| {
+                child
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("child module has a module name")
+            })
+            .collect();
+
+        // The key below a leaf child is just that leaf's parameters
+        let (child_key, owned_child_key): (Vec<_>, Vec<_>) = children
+            .iter()
+            .map(|child| {
+                if child.is_leaf() {
+                    (quote!(#Params<'a>), quote!(#OwnedParams))
+                } else {
+                    (quote!(#Key<'a>), quote!(#OwnedKey))
+                }
+            })
+            .unzip();
+
+        // The documentation of each child doubles as the help text for its subcommand
+        let context = self.child_context();
+        let child_docs: Vec<_> = children
+            .iter()
+            .map(|child| {
+                NodeInContextWithSettings {
+                    node: child,
+                    context,
+                    settings,
+                }
+                .docs()
+            })
+            .collect();
+
+        // Name the subcommands explicitly, so they match the subcommands for prefixes
+        let clap_names: Vec<_> = subkey
+            .iter()
+            .map(|name| {
+                if derives_clap_subcommand {
+                    let name = subcommand_name(name);
+                    quote!(#[clap(name = #name)])
+                } else {
+                    quote!()
+                }
+            })
+            .collect();
+
+        let phantom = uninhabited_if_empty(&subkey);
 
         tokens.extend(quote! {
             #[allow(non_camel_case_types)]
             #[non_exhaustive]
             #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            enum #SubKey<'a> {
-                #(#subkey(#subkey::#Key<'a>)),*
+            #vis enum #SubKey<'a> {
+                #(#child_docs #subkey(#subkey::#child_key),)*
+                #phantom
             }
         });
 
@@ -462,45 +1307,1716 @@ impl NodeInContextWithSettings<'_> {
             #[non_exhaustive]
             #derive_clap_subcommand
             #[derive(::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
-            enum #OwnedSubKey {
-                #(#subkey(#subkey::#OwnedKey)),*
+            #vis enum #OwnedSubKey {
+                #(#child_docs #clap_names #subkey(#subkey::#owned_child_key)),*
             }
         });
     }
 
-    fn child_modules(&self, tokens: &mut TokenStream) {
-        let Self {
-            node,
-            context,
-            settings,
-        } = self;
+    /// Whether this node gets a leaf enum: the root and the end of every segment with children.
+    fn has_leaf_enum(&self) -> bool {
+        (self.context.is_root() || self.ends_segment()) && !self.node.is_leaf()
+    }
 
-        if let Ok(Children::Below(children)) = &node.children {
-            // Figure out the context for the children
-            let context = Context {
-                depth: context.depth + 1,
-                remaining_param_count: match node.header.kind {
-                    Kind::Var(_) => context.remaining_param_count - 1,
-                    Kind::Static { param_count, .. } => param_count,
-                },
-            };
+    fn leaf_enum(&self, tokens: &mut TokenStream) {
+        let vis = self.item_visibility();
 
-            // Generate code for all the children
-            for child in children {
-                let mod_name = child
+        let Self { node, settings, .. } = self;
+
+        let Names { Leaf, .. } = &settings.names;
+
+        if !self.has_leaf_enum() {
+            return;
+        }
+
+        let children = match &node.children {
+            Ok(Children::Below(children)) => children.as_slice(),
+            _ => &[],
+        };
+
+        // Each child gets a variant holding all the parameters of its segment, and unless it is a
+        // leaf, the leaf enum of the end of its segment
+        let context = self.child_context();
+        let variants = children.iter().map(|child| {
+            let name = child
+                .header
+                .mod_name
+                .as_ref()
+                .expect("child module has a module name");
+            let docs = NodeInContextWithSettings {
+                node: child,
+                context,
+                settings,
+            }
+            .docs();
+
+            let params = child.segment_params();
+            let mut fields: Vec<_> = params
+                .iter()
+                .map(|param| {
+                    let header = &param.header;
+                    let field = header.mod_name.as_ref().expect("parameter has a name");
+                    let Kind::Var { ty, .. } = &header.kind else {
+                        unreachable!("parameter is always a var node")
+                    };
+                    let docs = &header.docs;
+                    let ty = owned_ty(ty);
+                    quote!(#(#[doc = #docs])* #field: #ty)
+                })
+                .collect();
+
+            if !child.segment_end().is_leaf() {
+                let path = segment_path(child);
+                fields.push(quote!(child: #(#path::)* #Leaf));
+            }
+
+            if fields.is_empty() {
+                quote!(#docs #name)
+            } else {
+                quote!(#docs #name { #(#fields),* })
+            }
+        });
+
+        tokens.extend(quote! {
+            /// Which leaf beneath this node a key is for, along with all the parameters of its key
+            /// from here on down.
+            #[allow(non_camel_case_types)]
+            #[derive(::core::clone::Clone)]
+            #vis enum #Leaf {
+                #(#variants,)*
+            }
+        });
+    }
+
+    fn leaf_from_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        let Names {
+            OwnedSubKey, Leaf, ..
+        } = &settings.names;
+
+        if !self.has_leaf_enum() {
+            return;
+        }
+
+        let children = match &node.children {
+            Ok(Children::Below(children)) => children.as_slice(),
+            _ => &[],
+        };
+
+        // Walk down each child's segment, collecting its parameters along the way
+        let arms = children.iter().map(|child| {
+            let name = child
+                .header
+                .mod_name
+                .as_ref()
+                .expect("child module has a module name");
+
+            // Duplicates are already an error, so there's no need to generate anything sensible
+            if child.children.is_err() {
+                return quote!(#OwnedSubKey::#name(_) => ::core::unreachable!(),);
+            }
+
+            let params = child.segment_params();
+            if params.is_empty() && child.is_leaf() {
+                return quote!(#OwnedSubKey::#name(_) => #Leaf::#name,);
+            }
+
+            let mut statements = vec![quote!(let (_, __child) = __key.into_parts();)];
+            let mut path = vec![name];
+            let mut fields = Vec::new();
+            for param in &params {
+                let field = param
                     .header
                     .mod_name
                     .as_ref()
-                    .expect("child has module name");
+                    .expect("parameter has a name");
+                statements.push(quote!(let #(#path::)* #OwnedSubKey::#field(__key) = __child;));
+                path.push(field);
+                if param.is_leaf() {
+                    statements.push(quote!(let #field = __key.#field;));
+                } else {
+                    statements.push(quote! {
+                        let (__params, __child) = __key.into_parts();
+                        let #field = __params.#field;
+                    });
+                }
+                fields.push(quote!(#field));
+            }
+
+            if !child.segment_end().is_leaf() {
+                fields.push(quote!(child: #(#path::)* #Leaf::from(__child)));
+            }
 
+            quote! {
+                #OwnedSubKey::#name(__key) => {
+                    #(#statements)*
+                    #Leaf::#name { #(#fields),* }
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            impl ::core::convert::From<#OwnedSubKey> for #Leaf {
+                fn from(__child: #OwnedSubKey) -> Self {
+                    match __child {
+                        #(#arms)*
+                    }
+                }
+            }
+        });
+    }
+
+    /// Call `f` on every node beneath this one at which a chain of leaf enums ends: every leaf,
+    /// and every segment without any children.
+    fn for_each_leaf(&self, f: &mut dyn FnMut(&NodeInContextWithSettings)) {
+        if let Ok(Children::Below(children)) = &self.node.children {
+            let context = self.child_context();
+            for child in children {
                 let child = NodeInContextWithSettings {
                     node: child,
                     context,
-                    settings,
+                    settings: self.settings,
                 };
-
-                tokens.extend(quote! {
-                    mod #mod_name {
+                match &child.node.children {
+                    Ok(Children::Below(children)) if !children.is_empty() => child.for_each_leaf(f),
+                    _ => f(&child),
+                }
+            }
+        }
+    }
+
+    fn root_visitor(&self, tokens: &mut TokenStream) {
+        let Self {
+            context, settings, ..
+        } = self;
+
+        // Only generate the visitor for the root of the schema
+        if !context.is_root() {
+            return;
+        }
+
+        let Names {
+            OwnedKey,
+            Leaf,
+            Visitor,
+            ..
+        } = &settings.names;
+
+        let vis = &settings.visibility;
+
+        let mut methods = Vec::new();
+        let mut arms = Vec::new();
+        self.for_each_leaf(&mut |leaf| {
+            let ancestry = &leaf.ancestry()[1..];
+
+            // Match on the nested leaf enums from the inside out, binding each parameter in turn
+            let mut bindings = Vec::new();
+            let mut args = Vec::new();
+            let mut segments = Vec::new();
+            let mut pattern: Option<TokenStream> = None;
+            let segment_starts: Vec<usize> = (0..ancestry.len())
+                .filter(|&i| matches!(ancestry[i].node.header.kind, Kind::Static { .. }))
+                .collect();
+            for (i, level) in ancestry.iter().enumerate() {
+                let header = &level.node.header;
+                let name = header.mod_name.as_ref().expect("non-root node has a name");
+                match &header.kind {
+                    Kind::Static { .. } => segments.push(name.clone()),
+                    Kind::Var { ty, .. } => {
+                        // Disambiguate parameters of the same name by the segment they belong to
+                        let segment = segments.last().expect("parameter follows a segment");
+                        let arg = if args.iter().any(|(arg, _)| arg == name) {
+                            format_ident!("{}_{}", segment.unraw(), name.unraw())
+                        } else {
+                            name.clone()
+                        };
+                        args.push((arg, owned_ty(ty)));
+                        bindings.push((i, format_ident!("__{}", bindings.len())));
+                    }
+                }
+            }
+
+            for (n, &start) in segment_starts.iter().enumerate().rev() {
+                let end = segment_starts.get(n + 1).copied().unwrap_or(ancestry.len());
+                let module: Vec<_> = ancestry[..start]
+                    .iter()
+                    .map(|level| level.node.header.mod_name.as_ref().expect("has a name"))
+                    .collect();
+                let name = ancestry[start]
+                    .node
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("has a name");
+                let mut fields: Vec<_> = bindings
+                    .iter()
+                    .filter(|(i, _)| (start..end).contains(i))
+                    .map(|(i, binding)| {
+                        let field = ancestry[*i].node.header.mod_name.as_ref();
+                        quote!(#field: #binding)
+                    })
+                    .collect();
+                match pattern.take() {
+                    Some(inner) => fields.push(quote!(child: #inner)),
+                    None => match &leaf.node.children {
+                        Ok(Children::Leaf(_)) => {}
+                        Ok(Children::Below(_)) => fields.push(quote!(child: __never)),
+                        Err(_) => fields.push(quote!(..)),
+                    },
+                }
+                pattern = Some(if fields.is_empty() {
+                    quote!(#(#module::)* #Leaf::#name)
+                } else {
+                    quote!(#(#module::)* #Leaf::#name { #(#fields),* })
+                });
+            }
+
+            let bound: Vec<_> = bindings.iter().map(|(_, binding)| binding).collect();
+            match &leaf.node.children {
+                Ok(Children::Leaf(_)) => {
+                    let method = visitor_method_name(&segments);
+                    let docs = leaf.docs();
+                    let (args, tys): (Vec<_>, Vec<_>) = args.into_iter().unzip();
+                    methods.push(quote! {
+                        #docs
+                        fn #method(&mut self, #(#args: #tys),*) -> Self::Output;
+                    });
+                    arms.push(quote!(#pattern => visitor.#method(#(#bound),*),));
+                }
+                // Segments without children have uninhabited leaf enums
+                Ok(Children::Below(_)) => arms.push(quote!(#pattern => match __never {},)),
+                // Duplicates are already an error, so there's no need to generate anything sensible
+                Err(_) => arms.push(quote!(#pattern => ::core::unreachable!(),)),
+            }
+        });
+
+        tokens.extend(quote! {
+            /// A visitor over the leaves of this schema, with a method for each leaf which is
+            /// called with all the parameters of its key.
+            ///
+            /// Because every leaf has a method which must be implemented, handling keys with a
+            /// visitor makes sure that no leaf is forgotten.
+            #vis trait #Visitor {
+                /// The result of visiting a key.
+                type Output;
+
+                #(#methods)*
+            }
+
+            impl #Leaf {
+                /// Call the method of the visitor for the leaf this is, with its parameters.
+                pub fn visit<V: #Visitor + ?::core::marker::Sized>(self, visitor: &mut V) -> V::Output {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+
+            impl #OwnedKey {
+                /// Call the method of the visitor for the leaf this key is for, with its
+                /// parameters.
+                pub fn visit<V: #Visitor + ?::core::marker::Sized>(self, visitor: &mut V) -> V::Output {
+                    #Leaf::from(self).visit(visitor)
+                }
+            }
+
+            impl ::core::convert::From<#OwnedKey> for #Leaf {
+                fn from(key: #OwnedKey) -> Self {
+                    #Leaf::from(key.child)
+                }
+            }
+        });
+    }
+
+    fn params_encode_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        let Names {
+            Params,
+            OwnedParams,
+            ..
+        } = &settings.names;
+
+        // The parameters of a node are what determine its segment of an encoded key: a static
+        // node is encoded as its name, and a parameter node as the value of its parameter
+        let (encode, owned_encode) = match &node.header.kind {
+            Kind::Var { .. } => {
+                let field = node
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("mod name is specified when params are present");
+                let encode = quote!(::schemata::Encode::encode(&self.#field, buf));
+                (encode.clone(), encode)
+            }
+            Kind::Static { .. } => match node.header.static_name() {
+                Some(name) => {
+                    let name = LitByteStr::new(name.value().as_bytes(), name.span());
+                    (
+                        quote!(buf.extend_from_slice(#name)),
+                        quote!(buf.extend_from_slice(#name)),
+                    )
+                }
+                // The root of the schema has no segment of its own
+                None => (quote!(), quote!()),
+            },
+        };
+
+        tokens.extend(quote! {
+            impl ::schemata::Encode for #Params<'_> {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    #encode;
+                }
+            }
+
+            impl ::schemata::Encode for #OwnedParams {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    #owned_encode;
+                }
+            }
+        });
+    }
+
+    fn key_encode_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            Key,
+            OwnedKey,
+            SubKey,
+            OwnedSubKey,
+            ..
+        } = &settings.names;
+
+        // A leaf key is just a wrapper around the complete key from the root
+        if node.is_leaf() {
+            tokens.extend(quote! {
+                impl ::schemata::Encode for #Key<'_> {
+                    fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                        ::schemata::Encode::encode(&self.key, buf);
+                    }
+                }
+
+                impl ::schemata::Encode for #OwnedKey {
+                    fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                        ::schemata::Encode::encode(&self.key, buf);
+                    }
+                }
+            });
+            return;
+        }
+
+        // Every segment but the root's is separated from the one below it
+        let separator = if context.is_root() {
+            quote!()
+        } else {
+            quote!(buf.push(::schemata::SEPARATOR);)
+        };
+
+        let subkey = self.child_mod_names();
+        let uninhabited = uninhabited_arm_if_empty(SubKey, &subkey);
+        let owned_scrutinee = owned_scrutinee(&subkey);
+
+        tokens.extend(quote! {
+            impl ::schemata::Encode for #Key<'_> {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    ::schemata::Encode::encode(&self.params, buf);
+                    #separator
+                    ::schemata::Encode::encode(&self.child, buf);
+                }
+            }
+
+            impl ::schemata::Encode for #OwnedKey {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    ::schemata::Encode::encode(&self.params, buf);
+                    #separator
+                    ::schemata::Encode::encode(&self.child, buf);
+                }
+            }
+
+            impl ::schemata::Encode for #SubKey<'_> {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    match self {
+                        #(#SubKey::#subkey(child) => ::schemata::Encode::encode(child, buf),)*
+                        #uninhabited
+                    }
+                }
+            }
+
+            impl ::schemata::Encode for #OwnedSubKey {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    match #owned_scrutinee {
+                        #(#OwnedSubKey::#subkey(child) => ::schemata::Encode::encode(child, buf),)*
+                    }
+                }
+            }
+        });
+    }
+
+    fn prefix_encode_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        // Don't generate these for leaves of the schema
+        if node.is_leaf() {
+            return;
+        }
+
+        let Names {
+            Prefix,
+            OwnedPrefix,
+            SubPrefix,
+            OwnedSubPrefix,
+            ..
+        } = &settings.names;
+
+        // Unlike a key, a prefix always ends in a separator (except at the root), so that it
+        // never matches a sibling whose name merely starts with the same bytes
+        let separator = if context.is_root() {
+            quote!()
+        } else {
+            quote!(buf.push(::schemata::SEPARATOR);)
+        };
+
+        let subprefix = self.internal_child_mod_names();
+        let uninhabited = uninhabited_arm_if_empty(SubPrefix, &subprefix);
+        let owned_scrutinee = owned_scrutinee(&subprefix);
+
+        tokens.extend(quote! {
+            impl ::schemata::Encode for #Prefix<'_> {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    ::schemata::Encode::encode(&self.params, buf);
+                    #separator
+                    if let ::core::option::Option::Some(child) = &self.child {
+                        ::schemata::Encode::encode(child, buf);
+                    }
+                }
+            }
+
+            impl ::schemata::Encode for #OwnedPrefix {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    ::schemata::Encode::encode(&self.params, buf);
+                    #separator
+                    if let ::core::option::Option::Some(child) = &self.child {
+                        ::schemata::Encode::encode(child, buf);
+                    }
+                }
+            }
+
+            impl ::schemata::Encode for #SubPrefix<'_> {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    match self {
+                        #(#SubPrefix::#subprefix(child) => ::schemata::Encode::encode(child, buf),)*
+                        #uninhabited
+                    }
+                }
+            }
+
+            impl ::schemata::Encode for #OwnedSubPrefix {
+                fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                    match #owned_scrutinee {
+                        #(#OwnedSubPrefix::#subprefix(child) => ::schemata::Encode::encode(child, buf),)*
+                    }
+                }
+            }
+        });
+    }
+
+    fn display_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            Params,
+            OwnedParams,
+            Key,
+            OwnedKey,
+            SubKey,
+            OwnedSubKey,
+            Prefix,
+            OwnedPrefix,
+            SubPrefix,
+            OwnedSubPrefix,
+            ..
+        } = &settings.names;
+
+        // Every type is displayed as the human-readable form of its encoding, and debugged the
+        // same way, since its structure is an implementation detail
+        let display = |ty: TokenStream, body: TokenStream| {
+            quote! {
+                impl ::core::fmt::Display for #ty {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        #body
+                    }
+                }
+
+                impl ::core::fmt::Debug for #ty {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        ::core::fmt::Display::fmt(self, f)
+                    }
+                }
+            }
+        };
+
+        // Static segments are displayed as their names, and parameters with their `Debug` impls
+        let params = match &node.header.kind {
+            Kind::Var { .. } => {
+                let field = node
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("mod name is specified when params are present");
+                quote!(::core::write!(f, "{:?}", self.#field))
+            }
+            Kind::Static { .. } => match node.header.static_name() {
+                Some(name) => quote!(f.write_str(#name)),
+                // The root of the schema has no segment of its own
+                None => quote!(::core::result::Result::Ok(())),
+            },
+        };
+        tokens.extend(display(quote!(#Params<'_>), params.clone()));
+        tokens.extend(display(quote!(#OwnedParams), params));
+
+        // A leaf key is just a wrapper around the complete key from the root
+        if node.is_leaf() {
+            let body = quote!(::core::fmt::Display::fmt(&self.key, f));
+            tokens.extend(display(quote!(#Key<'_>), body.clone()));
+            tokens.extend(display(quote!(#OwnedKey), body));
+            return;
+        }
+
+        // Mirror the encoding, segment by segment
+        let separator = if context.is_root() {
+            quote!()
+        } else {
+            quote!(f.write_str("/")?;)
+        };
+
+        let body = quote! {
+            ::core::fmt::Display::fmt(&self.params, f)?;
+            #separator
+            ::core::fmt::Display::fmt(&self.child, f)
+        };
+        tokens.extend(display(quote!(#Key<'_>), body.clone()));
+        tokens.extend(display(quote!(#OwnedKey), body));
+
+        let body = quote! {
+            ::core::fmt::Display::fmt(&self.params, f)?;
+            #separator
+            match &self.child {
+                ::core::option::Option::Some(child) => ::core::fmt::Display::fmt(child, f),
+                ::core::option::Option::None => ::core::result::Result::Ok(()),
+            }
+        };
+        tokens.extend(display(quote!(#Prefix<'_>), body.clone()));
+        tokens.extend(display(quote!(#OwnedPrefix), body));
+
+        let subkey = self.child_mod_names();
+        let uninhabited = uninhabited_arm_if_empty(SubKey, &subkey);
+        let scrutinee = owned_scrutinee(&subkey);
+        tokens.extend(display(
+            quote!(#SubKey<'_>),
+            quote! {
+                match self {
+                    #(#SubKey::#subkey(child) => ::core::fmt::Display::fmt(child, f),)*
+                    #uninhabited
+                }
+            },
+        ));
+        tokens.extend(display(
+            quote!(#OwnedSubKey),
+            quote! {
+                match #scrutinee {
+                    #(#OwnedSubKey::#subkey(child) => ::core::fmt::Display::fmt(child, f),)*
+                }
+            },
+        ));
+
+        let subprefix = self.internal_child_mod_names();
+        let uninhabited = uninhabited_arm_if_empty(SubPrefix, &subprefix);
+        let scrutinee = owned_scrutinee(&subprefix);
+        tokens.extend(display(
+            quote!(#SubPrefix<'_>),
+            quote! {
+                match self {
+                    #(#SubPrefix::#subprefix(child) => ::core::fmt::Display::fmt(child, f),)*
+                    #uninhabited
+                }
+            },
+        ));
+        tokens.extend(display(
+            quote!(#OwnedSubPrefix),
+            quote! {
+                match #scrutinee {
+                    #(#OwnedSubPrefix::#subprefix(child) => ::core::fmt::Display::fmt(child, f),)*
+                }
+            },
+        ));
+    }
+
+    fn order_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        let Names {
+            Key,
+            OwnedKey,
+            Prefix,
+            OwnedPrefix,
+            ..
+        } = &settings.names;
+
+        // Keys and prefixes are hashed and ordered by their encodings, so that they sort in the
+        // same order as they do in the store, consistently with equality
+        let order = |ty: TokenStream| {
+            quote! {
+                impl ::core::hash::Hash for #ty {
+                    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                        ::core::hash::Hash::hash(&::schemata::Encode::to_bytes(self), state);
+                    }
+                }
+
+                impl ::core::cmp::PartialOrd for #ty {
+                    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                        ::core::option::Option::Some(::core::cmp::Ord::cmp(self, other))
+                    }
+                }
+
+                impl ::core::cmp::Ord for #ty {
+                    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                        ::schemata::Encode::to_bytes(self).cmp(&::schemata::Encode::to_bytes(other))
+                    }
+                }
+            }
+        };
+
+        tokens.extend(order(quote!(#Key<'_>)));
+        tokens.extend(order(quote!(#OwnedKey)));
+
+        // Don't generate these for leaves of the schema, which have no prefixes
+        if !node.is_leaf() {
+            tokens.extend(order(quote!(#Prefix<'_>)));
+            tokens.extend(order(quote!(#OwnedPrefix)));
+        }
+    }
+
+    fn key_trait_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            Schema,
+            Key,
+            OwnedKey,
+            ..
+        } = &settings.names;
+
+        // Only leaves have a value type (duplicates have no type we could use)
+        let value = match &node.children {
+            Ok(Children::Leaf(value)) => value,
+            Ok(Children::Below(_)) | Err(_) => return,
+        };
+
+        let root = supers(context.depth);
+
+        tokens.extend(quote! {
+            impl ::schemata::Key for #Key<'_> {
+                type Schema = #root #Schema;
+                type Value = #value;
+            }
+
+            impl ::schemata::Key for #OwnedKey {
+                type Schema = #root #Schema;
+                type Value = #value;
+            }
+
+            impl ::schemata::OwnedKey for #OwnedKey {}
+        });
+    }
+
+    fn key_leaf_decode_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            OwnedKey,
+            OwnedSubKey,
+            ..
+        } = &settings.names;
+
+        if !node.is_leaf() {
+            return;
+        }
+
+        // Check whether a complete key is for this leaf by matching on each sub-key in turn, from
+        // the root all the way down to this leaf
+        let ancestry = self.ancestry();
+        let mut is_this_leaf = None;
+        for (i, level) in ancestry.iter().enumerate().skip(1).rev() {
+            let name = level
+                .node
+                .header
+                .mod_name
+                .as_ref()
+                .expect("non-root node always has a module name");
+            let up = supers(ancestry.len() - i);
+            is_this_leaf = Some(match is_this_leaf {
+                None => quote!(::core::matches!(&key.child, #up #OwnedSubKey::#name(_))),
+                Some(is_below) => quote! {
+                    ::core::matches!(&key.child, #up #OwnedSubKey::#name(key) if #is_below)
+                },
+            });
+        }
+
+        let root = supers(context.depth);
+
+        tokens.extend(quote! {
+            impl ::schemata::Decode for #OwnedKey {
+                fn decode(input: &mut &[u8]) -> ::core::result::Result<Self, ::schemata::DecodeError> {
+                    let key: #root #OwnedKey = ::schemata::Decode::decode(input)?;
+                    if #is_this_leaf {
+                        ::core::result::Result::Ok(#OwnedKey { key })
+                    } else {
+                        ::core::result::Result::Err(::schemata::DecodeError::DifferentLeaf)
+                    }
+                }
+            }
+        });
+    }
+
+    fn accessor_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            Schema,
+            Path,
+            OwnedPath,
+            Params,
+            OwnedParams,
+            Key,
+            OwnedKey,
+            SubKey,
+            OwnedSubKey,
+            Prefix,
+            OwnedPrefix,
+            SubPrefix,
+            OwnedSubPrefix,
+            ..
+        } = &settings.names;
+
+        let (parent, owned_parent) = if context.is_root() {
+            (quote!(#Schema), quote!(#Schema))
+        } else {
+            (quote!(super::#Path<'a>), quote!(super::#OwnedPath))
+        };
+
+        tokens.extend(quote! {
+            impl<'a> #Path<'a> {
+                /// The parameters of this path at this level.
+                pub fn params(&self) -> &#Params<'a> {
+                    &self.params
+                }
+
+                /// The path to the level above this one.
+                pub fn parent(&self) -> &#parent {
+                    &self.parent
+                }
+            }
+
+            impl #OwnedPath {
+                /// The parameters of this path at this level.
+                pub fn params(&self) -> &#OwnedParams {
+                    &self.params
+                }
+
+                /// The path to the level above this one.
+                pub fn parent(&self) -> &#owned_parent {
+                    &self.parent
+                }
+            }
+        });
+
+        // The key for a leaf just wraps the complete key from the root of the schema
+        if node.is_leaf() {
+            let root = supers(context.depth);
+
+            tokens.extend(quote! {
+                impl<'a> #Key<'a> {
+                    /// The complete key from the root of the schema, which can be matched on level
+                    /// by level.
+                    pub fn root(&self) -> &#root #Key<'a> {
+                        &self.key
+                    }
+
+                    /// Convert into the complete key from the root of the schema.
+                    pub fn into_root(self) -> #root #Key<'a> {
+                        self.key
+                    }
+                }
+
+                impl #OwnedKey {
+                    /// The complete key from the root of the schema, which can be matched on level
+                    /// by level.
+                    pub fn root(&self) -> &#root #OwnedKey {
+                        &self.key
+                    }
+
+                    /// Convert into the complete key from the root of the schema.
+                    pub fn into_root(self) -> #root #OwnedKey {
+                        self.key
+                    }
+                }
+            });
+            return;
+        }
+
+        tokens.extend(quote! {
+            impl<'a> #Key<'a> {
+                /// The parameters of this key at this level.
+                pub fn params(&self) -> &#Params<'a> {
+                    &self.params
+                }
+
+                /// The rest of this key, below this level.
+                pub fn child(&self) -> &#SubKey<'a> {
+                    &self.child
+                }
+            }
+
+            impl #OwnedKey {
+                /// The parameters of this key at this level.
+                pub fn params(&self) -> &#OwnedParams {
+                    &self.params
+                }
+
+                /// The rest of this key, below this level.
+                pub fn child(&self) -> &#OwnedSubKey {
+                    &self.child
+                }
+
+                /// Split this key into its parameters at this level, and the rest of it below.
+                pub fn into_parts(self) -> (#OwnedParams, #OwnedSubKey) {
+                    (self.params, self.child)
+                }
+            }
+
+            impl<'a> #Prefix<'a> {
+                /// The parameters of this prefix at this level.
+                pub fn params(&self) -> &#Params<'a> {
+                    &self.params
+                }
+
+                /// The rest of this prefix below this level, if it extends any further.
+                pub fn child(&self) -> ::core::option::Option<&#SubPrefix<'a>> {
+                    self.child.as_ref()
+                }
+            }
+
+            impl #OwnedPrefix {
+                /// The parameters of this prefix at this level.
+                pub fn params(&self) -> &#OwnedParams {
+                    &self.params
+                }
+
+                /// The rest of this prefix below this level, if it extends any further.
+                pub fn child(&self) -> ::core::option::Option<&#OwnedSubPrefix> {
+                    self.child.as_ref()
+                }
+            }
+        });
+    }
+
+    fn owned_conversion_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            Schema,
+            Path,
+            OwnedPath,
+            Params,
+            OwnedParams,
+            Key,
+            OwnedKey,
+            SubKey,
+            OwnedSubKey,
+            Prefix,
+            OwnedPrefix,
+            SubPrefix,
+            OwnedSubPrefix,
+            ..
+        } = &settings.names;
+
+        // Every pair of borrowed and owned types gets the same conversions, in terms of how to
+        // convert each one's fields
+        let conversions = |borrowed: TokenStream,
+                           owned: TokenStream,
+                           to_owned: TokenStream,
+                           as_borrowed: TokenStream| {
+            quote! {
+                impl<'a> #borrowed {
+                    /// Convert into the owned form, cloning any parameters.
+                    pub fn to_owned(&self) -> #owned {
+                        #to_owned
+                    }
+                }
+
+                impl #owned {
+                    /// Borrow as the borrowed form, which refers to the parameters held here.
+                    pub fn as_borrowed<'a>(&'a self) -> #borrowed {
+                        #as_borrowed
+                    }
+                }
+
+                impl<'a> ::core::convert::From<#borrowed> for #owned {
+                    fn from(borrowed: #borrowed) -> Self {
+                        borrowed.to_owned()
+                    }
+                }
+
+                impl<'a> ::core::convert::From<&'a #owned> for #borrowed {
+                    fn from(owned: &'a #owned) -> Self {
+                        owned.as_borrowed()
+                    }
+                }
+            }
+        };
+
+        let (params_to_owned, params_as_borrowed) = match &node.header.kind {
+            Kind::Var { ty, by_value } => {
+                let field = node
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("mod name is specified when params are present");
+                if *by_value {
+                    (
+                        quote!(#OwnedParams { #field: ::std::borrow::ToOwned::to_owned(&self.#field) }),
+                        quote! {
+                            #Params {
+                                #field: *::core::borrow::Borrow::<#ty>::borrow(&self.#field),
+                                __: ::core::marker::PhantomData,
+                            }
+                        },
+                    )
+                } else {
+                    (
+                        quote!(#OwnedParams { #field: ::std::borrow::ToOwned::to_owned(self.#field) }),
+                        quote!(#Params { #field: ::core::borrow::Borrow::borrow(&self.#field) }),
+                    )
+                }
+            }
+            Kind::Static { .. } => (
+                quote!(#OwnedParams {}),
+                quote!(#Params { __: ::core::marker::PhantomData }),
+            ),
+        };
+        tokens.extend(conversions(
+            quote!(#Params<'a>),
+            quote!(#OwnedParams),
+            params_to_owned,
+            params_as_borrowed,
+        ));
+
+        let (path_to_owned, path_as_borrowed) = if context.is_root() {
+            (quote!(#Schema), quote!(#Schema))
+        } else {
+            (
+                quote!(self.parent.to_owned()),
+                quote!(self.parent.as_borrowed()),
+            )
+        };
+        tokens.extend(conversions(
+            quote!(#Path<'a>),
+            quote!(#OwnedPath),
+            quote!(#OwnedPath {
+                params: self.params.to_owned(),
+                parent: #path_to_owned,
+            }),
+            quote!(#Path {
+                params: self.params.as_borrowed(),
+                parent: #path_as_borrowed,
+            }),
+        ));
+
+        // A leaf key is just a wrapper around the complete key from the root
+        if node.is_leaf() {
+            tokens.extend(conversions(
+                quote!(#Key<'a>),
+                quote!(#OwnedKey),
+                quote!(#OwnedKey { key: self.key.to_owned() }),
+                quote!(#Key { key: self.key.as_borrowed() }),
+            ));
+            return;
+        }
+
+        tokens.extend(conversions(
+            quote!(#Key<'a>),
+            quote!(#OwnedKey),
+            quote!(#OwnedKey {
+                params: self.params.to_owned(),
+                child: self.child.to_owned(),
+            }),
+            quote!(#Key {
+                params: self.params.as_borrowed(),
+                child: self.child.as_borrowed(),
+            }),
+        ));
+
+        tokens.extend(conversions(
+            quote!(#Prefix<'a>),
+            quote!(#OwnedPrefix),
+            quote!(#OwnedPrefix {
+                params: self.params.to_owned(),
+                child: self.child.as_ref().map(|child| child.to_owned()),
+            }),
+            quote!(#Prefix {
+                params: self.params.as_borrowed(),
+                child: self.child.as_ref().map(|child| child.as_borrowed()),
+            }),
+        ));
+
+        let subkey = self.child_mod_names();
+        let uninhabited = uninhabited_arm_if_empty(SubKey, &subkey);
+        let scrutinee = owned_scrutinee(&subkey);
+        tokens.extend(conversions(
+            quote!(#SubKey<'a>),
+            quote!(#OwnedSubKey),
+            quote! {
+                match self {
+                    #(#SubKey::#subkey(child) => #OwnedSubKey::#subkey(child.to_owned()),)*
+                    #uninhabited
+                }
+            },
+            quote! {
+                match #scrutinee {
+                    #(#OwnedSubKey::#subkey(child) => #SubKey::#subkey(child.as_borrowed()),)*
+                }
+            },
+        ));
+
+        let subprefix = self.internal_child_mod_names();
+        let uninhabited = uninhabited_arm_if_empty(SubPrefix, &subprefix);
+        let scrutinee = owned_scrutinee(&subprefix);
+        tokens.extend(conversions(
+            quote!(#SubPrefix<'a>),
+            quote!(#OwnedSubPrefix),
+            quote! {
+                match self {
+                    #(#SubPrefix::#subprefix(child) => #OwnedSubPrefix::#subprefix(child.to_owned()),)*
+                    #uninhabited
+                }
+            },
+            quote! {
+                match #scrutinee {
+                    #(#OwnedSubPrefix::#subprefix(child) => #SubPrefix::#subprefix(child.as_borrowed()),)*
+                }
+            },
+        ));
+    }
+
+    fn path_trait_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            context, settings, ..
+        } = self;
+
+        let Names {
+            Schema,
+            Path,
+            OwnedPath,
+            Params,
+            OwnedParams,
+            ..
+        } = &settings.names;
+
+        let root = supers(context.depth);
+
+        tokens.extend(quote! {
+            impl<'a> ::schemata::Path for #Path<'a> {
+                type Schema = #root #Schema;
+                type Params = #Params<'a>;
+            }
+
+            impl ::schemata::Path for #OwnedPath {
+                type Schema = #root #Schema;
+                type Params = #OwnedParams;
+            }
+        });
+    }
+
+    fn params_trait_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        let Names {
+            Params,
+            OwnedParams,
+            ..
+        } = &settings.names;
+
+        let name = match &node.header.kind {
+            Kind::Var { .. } => node
+                .header
+                .mod_name
+                .as_ref()
+                .map(|mod_name| mod_name.unraw().to_string())
+                .expect("mod name is specified when params are present"),
+            Kind::Static { .. } => node
+                .header
+                .static_name()
+                .map(|name| name.value())
+                .unwrap_or_default(),
+        };
+
+        tokens.extend(quote! {
+            impl ::schemata::Params for #Params<'_> {
+                const NAME: &'static str = #name;
+            }
+
+            impl ::schemata::Params for #OwnedParams {
+                const NAME: &'static str = #name;
+            }
+
+            impl ::schemata::OwnedParams for #OwnedParams {}
+        });
+    }
+
+    fn prefix_trait_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            context, settings, ..
+        } = self;
+
+        // Only the root prefix is a complete prefix; the others are only parts of it
+        if !context.is_root() {
+            return;
+        }
+
+        let Names {
+            Schema,
+            Prefix,
+            OwnedPrefix,
+            ..
+        } = &settings.names;
+
+        tokens.extend(quote! {
+            impl ::schemata::Prefix for #Prefix<'_> {
+                type Schema = #Schema;
+            }
+
+            impl ::schemata::Prefix for #OwnedPrefix {
+                type Schema = #Schema;
+            }
+        });
+    }
+
+    fn owned_sub_key_args_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        if !settings.extensions.clap || !self.only_child_is_param() {
+            return;
+        }
+
+        let Names {
+            OwnedKey,
+            OwnedParams,
+            OwnedSubKey,
+            ..
+        } = &settings.names;
+
+        let Ok(Children::Below(children)) = &node.children else {
+            unreachable!("node with a parameter child has children below it");
+        };
+        let child = &children[0];
+        let name = child
+            .header
+            .mod_name
+            .as_ref()
+            .expect("child module has a module name");
+        let child_key = if child.is_leaf() {
+            quote!(#name::#OwnedParams)
+        } else {
+            quote!(#name::#OwnedKey)
+        };
+
+        // A parameter isn't a subcommand of its own: there's only one way to continue from here,
+        // so its arguments (and those of its own children) are given directly to this command
+        tokens.extend(quote! {
+            impl ::schemata::clap::FromArgMatches for #OwnedSubKey {
+                fn from_arg_matches(
+                    matches: &::schemata::clap::ArgMatches,
+                ) -> ::core::result::Result<Self, ::schemata::clap::Error> {
+                    ::core::result::Result::Ok(#OwnedSubKey::#name(
+                        <#child_key as ::schemata::clap::FromArgMatches>::from_arg_matches(matches)?,
+                    ))
+                }
+
+                fn update_from_arg_matches(
+                    &mut self,
+                    matches: &::schemata::clap::ArgMatches,
+                ) -> ::core::result::Result<(), ::schemata::clap::Error> {
+                    match self {
+                        #OwnedSubKey::#name(child) => {
+                            ::schemata::clap::FromArgMatches::update_from_arg_matches(child, matches)
+                        }
+                    }
+                }
+            }
+
+            impl ::schemata::clap::Args for #OwnedSubKey {
+                fn augment_args(command: ::schemata::clap::Command) -> ::schemata::clap::Command {
+                    <#child_key as ::schemata::clap::Args>::augment_args(command)
+                }
+
+                fn augment_args_for_update(
+                    command: ::schemata::clap::Command,
+                ) -> ::schemata::clap::Command {
+                    <#child_key as ::schemata::clap::Args>::augment_args_for_update(command)
+                }
+            }
+        });
+    }
+
+    fn owned_key_text_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let children = match &node.children {
+            Ok(Children::Below(children)) if settings.extensions.cli => children,
+            _ => return,
+        };
+
+        let Names {
+            OwnedKey,
+            OwnedSubKey,
+            ..
+        } = &settings.names;
+
+        // Only the leaf beneath this node knows the type of its value, so each node dispatches to
+        // the child its key continues into, until reaching the parent of the leaf
+        let subkey = self.child_mod_names();
+        let (from_text, to_text): (Vec<_>, Vec<_>) = children
+            .iter()
+            .map(|child| match &child.children {
+                Ok(Children::Leaf(ty)) => (
+                    quote! {
+                        let value = <#ty as ::schemata::cli::TextValue>::from_text(text)?;
+                        let mut bytes = ::std::vec::Vec::new();
+                        ::schemata::Value::encode_value(&value, &mut bytes);
+                        ::core::result::Result::Ok(bytes)
+                    },
+                    quote! {
+                        let value = <#ty as ::schemata::Value>::decode_value(bytes)?;
+                        ::core::result::Result::Ok(::schemata::cli::TextValue::to_text(&value))
+                    },
+                ),
+                Ok(Children::Below(_)) => (
+                    quote!(child.leaf_value_from_text(text)),
+                    quote!(child.leaf_value_to_text(bytes)),
+                ),
+                // Duplicates are already an error, so there's no need to generate anything sensible
+                Err(_) => (
+                    quote!(::core::unreachable!()),
+                    quote!(::core::unreachable!()),
+                ),
+            })
+            .unzip();
+
+        let scrutinee = if subkey.is_empty() {
+            quote!(self.child)
+        } else {
+            quote!(&self.child)
+        };
+        let visibility = if context.is_root() {
+            quote!()
+        } else {
+            quote!(pub(super))
+        };
+
+        tokens.extend(quote! {
+            impl #OwnedKey {
+                #[allow(unused_variables)]
+                #visibility fn leaf_value_from_text(
+                    &self,
+                    text: &str,
+                ) -> ::core::result::Result<::std::vec::Vec<u8>, ::schemata::ValueError> {
+                    match #scrutinee {
+                        #(#OwnedSubKey::#subkey(child) => { #from_text })*
+                    }
+                }
+
+                #[allow(unused_variables)]
+                #visibility fn leaf_value_to_text(
+                    &self,
+                    bytes: &[u8],
+                ) -> ::core::result::Result<::std::string::String, ::schemata::ValueError> {
+                    match #scrutinee {
+                        #(#OwnedSubKey::#subkey(child) => { #to_text })*
+                    }
+                }
+            }
+        });
+
+        if context.is_root() {
+            tokens.extend(quote! {
+                impl ::schemata::cli::TextKey for #OwnedKey {
+                    fn value_from_text(
+                        &self,
+                        text: &str,
+                    ) -> ::core::result::Result<::std::vec::Vec<u8>, ::schemata::ValueError> {
+                        self.leaf_value_from_text(text)
+                    }
+
+                    fn value_to_text(
+                        &self,
+                        bytes: &[u8],
+                    ) -> ::core::result::Result<::std::string::String, ::schemata::ValueError> {
+                        self.leaf_value_to_text(bytes)
+                    }
+                }
+            });
+        }
+    }
+
+    fn owned_prefix_args_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        if !settings.extensions.cli || node.is_leaf() {
+            return;
+        }
+
+        let Names {
+            OwnedParams,
+            OwnedPrefix,
+            OwnedSubPrefix,
+            ..
+        } = &settings.names;
+
+        let subprefix = self.internal_child_mod_names();
+
+        // A prefix can stop at any node, so unlike for keys, every child is optional
+        let (augment, child) = if self.only_child_is_param() && !subprefix.is_empty() {
+            // The parameter of the only child is flattened into this command, as it is for keys,
+            // and the prefix continues into the child only if it is given
+            let name = subprefix[0];
+            let id = name.unraw().to_string();
+
+            // Parameters must be given in order, since a prefix can't skip any of them
+            let requires = match node.header.kind {
+                Kind::Var { .. } => {
+                    let parent_id = node
+                        .header
+                        .mod_name
+                        .as_ref()
+                        .expect("parameter has a name")
+                        .unraw()
+                        .to_string();
+                    quote!(.mut_arg(#id, |arg| arg.requires(#parent_id)))
+                }
+                Kind::Static { .. } => quote!(),
+            };
+
+            (
+                quote! {
+                    let command =
+                        <#name::#OwnedParams as ::schemata::clap::Args>::augment_args_for_update(command)
+                            #requires;
+                    <#name::#OwnedPrefix as ::schemata::clap::Args>::augment_args(command)
+                },
+                quote! {
+                    if matches.contains_id(#id) {
+                        ::core::option::Option::Some(#OwnedSubPrefix::#name(
+                            <#name::#OwnedPrefix as ::schemata::clap::FromArgMatches>::from_arg_matches(matches)?,
+                        ))
+                    } else if matches.subcommand_name().is_some() {
+                        return ::core::result::Result::Err(::schemata::clap::Error::raw(
+                            ::schemata::clap::error::ErrorKind::MissingRequiredArgument,
+                            ::core::concat!("the argument '--", #id, "' is required to continue the prefix\n"),
+                        ));
+                    } else {
+                        ::core::option::Option::None
+                    }
+                },
+            )
+        } else {
+            // Each child with a prefix of its own is an optional subcommand
+            let subcommand: Vec<_> = subprefix.iter().map(|name| subcommand_name(name)).collect();
+            (
+                quote! {
+                    command
+                        #(.subcommand(<#subprefix::#OwnedPrefix as ::schemata::clap::Args>::augment_args(
+                            ::schemata::clap::Command::new(#subcommand),
+                        )))*
+                },
+                quote! {
+                    match matches.subcommand() {
+                        #(::core::option::Option::Some((#subcommand, matches)) => {
+                            ::core::option::Option::Some(#OwnedSubPrefix::#subprefix(
+                                <#subprefix::#OwnedPrefix as ::schemata::clap::FromArgMatches>::from_arg_matches(matches)?,
+                            ))
+                        })*
+                        ::core::option::Option::Some((name, _)) => {
+                            return ::core::result::Result::Err(::schemata::clap::Error::raw(
+                                ::schemata::clap::error::ErrorKind::InvalidSubcommand,
+                                ::std::format!("unrecognized subcommand '{name}'\n"),
+                            ));
+                        }
+                        ::core::option::Option::None => ::core::option::Option::None,
+                    }
+                },
+            )
+        };
+
+        tokens.extend(quote! {
+            impl ::schemata::clap::FromArgMatches for #OwnedPrefix {
+                fn from_arg_matches(
+                    matches: &::schemata::clap::ArgMatches,
+                ) -> ::core::result::Result<Self, ::schemata::clap::Error> {
+                    let params =
+                        <#OwnedParams as ::schemata::clap::FromArgMatches>::from_arg_matches(matches)?;
+                    let child = #child;
+                    ::core::result::Result::Ok(#OwnedPrefix { params, child })
+                }
+
+                fn update_from_arg_matches(
+                    &mut self,
+                    matches: &::schemata::clap::ArgMatches,
+                ) -> ::core::result::Result<(), ::schemata::clap::Error> {
+                    *self = <Self as ::schemata::clap::FromArgMatches>::from_arg_matches(matches)?;
+                    ::core::result::Result::Ok(())
+                }
+            }
+
+            impl ::schemata::clap::Args for #OwnedPrefix {
+                fn augment_args(command: ::schemata::clap::Command) -> ::schemata::clap::Command {
+                    #augment
+                }
+
+                fn augment_args_for_update(
+                    command: ::schemata::clap::Command,
+                ) -> ::schemata::clap::Command {
+                    <Self as ::schemata::clap::Args>::augment_args(command)
+                }
+            }
+        });
+    }
+
+    fn params_eq_impls(&self, tokens: &mut TokenStream) {
+        let Self { settings, .. } = self;
+
+        let Names {
+            Params,
+            OwnedParams,
+            ..
+        } = &settings.names;
+
+        // Two sets of parameters are equal exactly when they refer to the same location in the
+        // store, i.e. when they have the same encoding
+        tokens.extend(quote! {
+            impl ::core::cmp::PartialEq for #Params<'_> {
+                fn eq(&self, other: &Self) -> bool {
+                    ::schemata::Encode::to_bytes(self) == ::schemata::Encode::to_bytes(other)
+                }
+            }
+
+            impl ::core::cmp::Eq for #Params<'_> {}
+
+            impl ::core::cmp::PartialEq for #OwnedParams {
+                fn eq(&self, other: &Self) -> bool {
+                    ::schemata::Encode::to_bytes(self) == ::schemata::Encode::to_bytes(other)
+                }
+            }
+
+            impl ::core::cmp::Eq for #OwnedParams {}
+        });
+    }
+
+    fn params_decode_impls(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        let Names { OwnedParams, .. } = &settings.names;
+
+        // Decoding parameters consumes this node's segment of the key, mirroring `Encode`
+        let decode = match &node.header.kind {
+            Kind::Var { .. } => {
+                let field = node
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("mod name is specified when params are present");
+                quote! {
+                    ::core::result::Result::Ok(#OwnedParams {
+                        #field: ::schemata::Decode::decode(input)?,
+                    })
+                }
+            }
+            Kind::Static { .. } => {
+                let check_name = node
+                    .header
+                    .static_name()
+                    .map(|name| quote!(::schemata::decode_segment(input, &[#name])?;));
+                quote! {
+                    #check_name
+                    ::core::result::Result::Ok(#OwnedParams {})
+                }
+            }
+        };
+
+        tokens.extend(quote! {
+            impl ::schemata::Decode for #OwnedParams {
+                fn decode(input: &mut &[u8]) -> ::core::result::Result<Self, ::schemata::DecodeError> {
+                    #decode
+                }
+            }
+        });
+    }
+
+    fn key_decode_impls(&self, tokens: &mut TokenStream) {
+        let Self {
+            node,
+            context,
+            settings,
+        } = self;
+
+        let Names {
+            OwnedKey,
+            OwnedSubKey,
+            ..
+        } = &settings.names;
+
+        let children = match &node.children {
+            Ok(Children::Below(children)) => children,
+            // Leaves are decoded as part of the complete key from the root of the schema
+            Ok(Children::Leaf(_)) | Err(_) => return,
+        };
+
+        let separator = if context.is_root() {
+            quote!()
+        } else {
+            quote!(::schemata::decode_separator(input)?;)
+        };
+
+        // Try each child in turn, skipping static children whose names don't match: if a child
+        // matches but fails to decode, we keep looking in case a later child matches too, but
+        // report the first such error if none succeed
+        let expected: Vec<_> = children
+            .iter()
+            .filter_map(|child| child.header.static_name())
+            .collect();
+        let attempts = children.iter().map(|child| {
+            let name = child
+                .header
+                .mod_name
+                .as_ref()
+                .expect("child module has a module name");
+            let attempt = quote! {
+                let mut rest = *input;
+                match ::schemata::Decode::decode(&mut rest) {
+                    ::core::result::Result::Ok(child) => {
+                        *input = rest;
+                        return ::core::result::Result::Ok(#OwnedSubKey::#name(child));
+                    }
+                    ::core::result::Result::Err(e) => error = error.or(::core::option::Option::Some(e)),
+                }
+            };
+            match child.header.static_name() {
+                Some(static_name) => quote! {
+                    if ::schemata::decode_segment(&mut { *input }, &[#static_name]).is_ok() {
+                        #attempt
+                    }
+                },
+                None => attempt,
+            }
+        });
+
+        tokens.extend(quote! {
+            impl ::schemata::Decode for #OwnedKey {
+                fn decode(input: &mut &[u8]) -> ::core::result::Result<Self, ::schemata::DecodeError> {
+                    let params = ::schemata::Decode::decode(input)?;
+                    #separator
+                    let child = ::schemata::Decode::decode(input)?;
+                    ::core::result::Result::Ok(#OwnedKey { params, child })
+                }
+            }
+
+            impl ::schemata::Decode for #OwnedSubKey {
+                #[allow(unused_mut)]
+                fn decode(input: &mut &[u8]) -> ::core::result::Result<Self, ::schemata::DecodeError> {
+                    let mut error = ::core::option::Option::None;
+                    #({ #attempts })*
+                    ::core::result::Result::Err(error.unwrap_or(
+                        ::schemata::DecodeError::NoMatchingSegment {
+                            expected: &[#(#expected),*],
+                        }
+                    ))
+                }
+            }
+        });
+    }
+
+    fn child_modules(&self, tokens: &mut TokenStream) {
+        let Self { node, settings, .. } = self;
+
+        if let Ok(Children::Below(children)) = &node.children {
+            // Figure out the context for the children
+            let context = self.child_context();
+
+            // Generate code for all the children
+            for child in children {
+                let mod_name = child
+                    .header
+                    .mod_name
+                    .as_ref()
+                    .expect("child has module name");
+
+                let child = NodeInContextWithSettings {
+                    node: child,
+                    context,
+                    settings,
+                };
+                let docs = child.docs();
+
+                // The module of a segment is as visible as the segment, and the modules of its
+                // parameters are nested inside that one, so they are no more visible than it is
+                let vis = match child.node.header.kind {
+                    Kind::Static { .. } => {
+                        visibility_beneath(&child.segment_visibility(), self.depth_in_segment())
+                    }
+                    Kind::Var { .. } => quote!(pub),
+                };
+
+                // Types in the schema are written relative to the invocation of the macro, so make
+                // everything in scope there available in scope here
+                tokens.extend(quote! {
+                    #docs
+                    #vis mod #mod_name {
+                        #[allow(unused_imports)]
+                        use super::*;
+
                         #child
                     }
                 })
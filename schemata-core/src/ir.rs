@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use proc_macro2::Span;
 use proc_macro_error::emit_error;
 use quote::format_ident;
 use syn::{
+    ext::IdentExt,
+    parse_quote,
     punctuated::{Pair, Punctuated},
     spanned::Spanned,
-    Ident, LitStr, Type,
+    Attribute, Expr, ExprLit, Ident, Lit, LitStr, Meta, MetaList, MetaNameValue, NestedMeta, Path,
+    Type, TypeArray, TypeGroup, TypeParen, TypePath, TypeTuple, Visibility,
 };
 
 use crate::syntax;
@@ -19,6 +22,7 @@ pub struct Ir {
 pub struct Settings {
     pub names: Names,
     pub extensions: Extensions,
+    pub visibility: Visibility,
 }
 
 #[allow(non_snake_case)]
@@ -36,11 +40,14 @@ pub struct Names {
     pub OwnedSubPrefix: Ident,
     pub SubKey: Ident,
     pub OwnedSubKey: Ident,
+    pub Leaf: Ident,
+    pub Visitor: Ident,
 }
 
 #[derive(Default, Clone)]
 pub struct Extensions {
     pub clap: bool,
+    pub cli: bool,
 }
 
 impl Default for Names {
@@ -59,10 +66,232 @@ impl Default for Names {
             OwnedSubPrefix: format_ident!("OwnedSubPrefix"),
             SubKey: format_ident!("SubKey"),
             OwnedSubKey: format_ident!("OwnedSubKey"),
+            Leaf: format_ident!("Leaf"),
+            Visitor: format_ident!("Visitor"),
         }
     }
 }
 
+impl Names {
+    /// All the names, each paired with the name of the setting that configures it.
+    fn all(&self) -> [(&'static str, &Ident); 15] {
+        [
+            ("Schema", &self.Schema),
+            ("Path", &self.Path),
+            ("OwnedPath", &self.OwnedPath),
+            ("Prefix", &self.Prefix),
+            ("OwnedPrefix", &self.OwnedPrefix),
+            ("Key", &self.Key),
+            ("OwnedKey", &self.OwnedKey),
+            ("Params", &self.Params),
+            ("OwnedParams", &self.OwnedParams),
+            ("SubPrefix", &self.SubPrefix),
+            ("OwnedSubPrefix", &self.OwnedSubPrefix),
+            ("SubKey", &self.SubKey),
+            ("OwnedSubKey", &self.OwnedSubKey),
+            ("Leaf", &self.Leaf),
+            ("Visitor", &self.Visitor),
+        ]
+    }
+
+    /// All the names, each paired with the name of the setting that configures it, mutably.
+    fn all_mut(&mut self) -> [(&'static str, &mut Ident); 15] {
+        [
+            ("Schema", &mut self.Schema),
+            ("Path", &mut self.Path),
+            ("OwnedPath", &mut self.OwnedPath),
+            ("Prefix", &mut self.Prefix),
+            ("OwnedPrefix", &mut self.OwnedPrefix),
+            ("Key", &mut self.Key),
+            ("OwnedKey", &mut self.OwnedKey),
+            ("Params", &mut self.Params),
+            ("OwnedParams", &mut self.OwnedParams),
+            ("SubPrefix", &mut self.SubPrefix),
+            ("OwnedSubPrefix", &mut self.OwnedSubPrefix),
+            ("SubKey", &mut self.SubKey),
+            ("OwnedSubKey", &mut self.OwnedSubKey),
+            ("Leaf", &mut self.Leaf),
+            ("Visitor", &mut self.Visitor),
+        ]
+    }
+
+    /// Apply a single `Setting = "Name"` from a `names(...)` list, returning the setting that was
+    /// configured, or emitting an error if it's malformed.
+    fn configure(&mut self, name: &NestedMeta) -> Option<&'static str> {
+        let (path, value) = match name {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(value),
+                ..
+            })) => (path, value),
+            _ => {
+                emit_error!(
+                    name,
+                    "malformed name setting";
+                    help = "specify a name for a generated type, like `Key = \"StateKey\"`";
+                );
+                return None;
+            }
+        };
+
+        let settings = self
+            .all()
+            .map(|(setting, _)| format!("`{setting}`"))
+            .join(", ");
+        let Some((setting, field)) = self
+            .all_mut()
+            .into_iter()
+            .find(|(setting, _)| path.is_ident(setting))
+        else {
+            emit_error!(
+                path,
+                "unknown name setting";
+                help = "the names which can be set are: {}", settings;
+            );
+            return None;
+        };
+
+        match value.parse::<Ident>() {
+            Ok(ident) => *field = ident,
+            Err(_) => {
+                emit_error!(value, "name must be a valid Rust identifier");
+                return None;
+            }
+        }
+
+        Some(setting)
+    }
+
+    /// Emit errors for any names which would collide with each other, preferring to point at one
+    /// that was explicitly configured.
+    fn check_collisions(&self, configured: &HashSet<&'static str>) {
+        let all = self.all();
+        for (i, (setting, name)) in all.iter().enumerate() {
+            for (other_setting, other_name) in &all[i + 1..] {
+                if name != other_name {
+                    continue;
+                }
+                let (setting, other_setting, name) = if configured.contains(other_setting) {
+                    (other_setting, setting, other_name)
+                } else {
+                    (setting, other_setting, name)
+                };
+                emit_error!(
+                    name,
+                    "name `{}` for `{}` collides with the name for `{}`",
+                    name, setting, other_setting;
+                    help = "choose a different name for one of them";
+                );
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// Parse the settings from the inner `#![schemata(...)]` attributes of a schema, emitting
+    /// errors for anything unrecognized.
+    fn from_attrs(attrs: &[Attribute]) -> Self {
+        let mut settings = Settings {
+            names: Names::default(),
+            extensions: Extensions::default(),
+            visibility: parse_quote!(pub),
+        };
+        let mut configured = HashSet::new();
+
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("schemata")) {
+            let nested = match attr.parse_meta() {
+                Ok(Meta::List(MetaList { nested, .. })) => nested,
+                _ => {
+                    emit_error!(
+                        attr,
+                        "malformed `schemata` attribute";
+                        help = "specify settings for the schema, like `#![schemata(names(Key = \"StateKey\"))]`";
+                    );
+                    continue;
+                }
+            };
+
+            for setting in nested {
+                match setting {
+                    NestedMeta::Meta(Meta::List(MetaList { path, nested, .. }))
+                        if path.is_ident("names") =>
+                    {
+                        for name in nested {
+                            if let Some(setting) = settings.names.configure(&name) {
+                                if !configured.insert(setting) {
+                                    emit_error!(
+                                        name,
+                                        "name `{}` is configured more than once",
+                                        setting
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("clap") => {
+                        settings.extensions.clap = clap_enabled(&path);
+                    }
+                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                        path,
+                        lit: Lit::Str(visibility),
+                        ..
+                    })) if path.is_ident("visibility") => match visibility.parse() {
+                        Ok(visibility) => settings.visibility = visibility,
+                        Err(_) => emit_error!(
+                            visibility,
+                            "invalid visibility";
+                            help = "specify a visibility like `pub` or `pub(crate)`, or the empty string for private";
+                        ),
+                    },
+                    // The command-line interface is built on top of the `clap` extension
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("cli") => {
+                        settings.extensions.clap = clap_enabled(&path);
+                        settings.extensions.cli = settings.extensions.clap;
+                    }
+                    _ => emit_error!(
+                        setting,
+                        "unknown setting for schema";
+                        help = "the available settings are: `names(...)`, `visibility = \"...\"`, `clap`, `cli`";
+                    ),
+                }
+            }
+        }
+
+        settings.names.check_collisions(&configured);
+
+        settings
+    }
+}
+
+/// Whether the `clap` feature of `schemata` is enabled, emitting an error at the setting which needs
+/// it if not: the code generated for the setting would otherwise fail to resolve in many places.
+fn clap_enabled(setting: &Path) -> bool {
+    if !cfg!(feature = "clap") {
+        emit_error!(
+            setting,
+            "the `{}` setting requires the `clap` feature of `schemata`",
+            setting.get_ident().expect("setting is a single identifier");
+            help = "enable it in `Cargo.toml`, like `schemata = { version = \"...\", features = [\"clap\"] }`";
+        );
+    }
+    cfg!(feature = "clap")
+}
+
+/// The methods of generated paths other than those which navigate to their children, which no
+/// segment can share a name with.
+const PATH_METHODS: &[&str] = &[
+    "prefix",
+    "into_prefix",
+    "prefix_with_child",
+    "into_prefix_with_child",
+    "key_with_child",
+    "into_key_with_child",
+    "params",
+    "parent",
+    "to_owned",
+    "as_borrowed",
+];
+
 pub struct Node {
     pub header: Header,
     pub children: Result<Children, Duplicate>,
@@ -72,12 +301,17 @@ pub struct Duplicate;
 
 pub struct Header {
     pub docs: Vec<LitStr>,
+    pub vis: Option<Visibility>,
     pub mod_name: Option<Ident>,
     pub kind: Kind,
 }
 
 pub enum Kind {
-    Var(Box<Type>),
+    Var {
+        ty: Box<Type>,
+        /// Whether the parameter is held by value in borrowed paths, rather than by reference.
+        by_value: bool,
+    },
     Static {
         renamed: Option<LitStr>,
         param_count: usize,
@@ -100,12 +334,12 @@ impl Node {
 
                 if let Some(ref mod_name) = child.header.mod_name {
                     if let Some(previous_mod_name) =
-                        seen_mod_names.insert(mod_name.to_string(), mod_name.span())
+                        seen_mod_names.insert(mod_name.unraw().to_string(), mod_name.span())
                     {
                         child.children = Err(Duplicate);
 
                         // Put the old name back so we get a consistent hint span
-                        seen_mod_names.insert(mod_name.to_string(), previous_mod_name);
+                        seen_mod_names.insert(mod_name.unraw().to_string(), previous_mod_name);
 
                         // We don't emit an error about this issue; the Rust compiler will complain
                         // because the generated code will contain a duplicate module
@@ -136,19 +370,20 @@ impl Node {
                     }
                 } else if let Some(ref mod_name) = child.header.mod_name {
                     if let Some(previous_actual_name) =
-                        seen_actual_names.insert(mod_name.to_string(), mod_name.span())
+                        seen_actual_names.insert(mod_name.unraw().to_string(), mod_name.span())
                     {
                         child.children = Err(Duplicate);
 
                         // Put the old name back so we get a consistent hint span
-                        seen_actual_names.insert(mod_name.to_string(), previous_actual_name);
+                        seen_actual_names
+                            .insert(mod_name.unraw().to_string(), previous_actual_name);
 
                         // The Rust compiler isn't going to check strings for equality, so we need
                         // to complain about this issue by making our own error
                         emit_error!(
                             mod_name,
                             "duplicate path segment: \"{}\"",
-                            mod_name;
+                            mod_name.unraw();
                             help = "use a different name for this path segment, or merge the two namespaces";
                         );
                     }
@@ -157,6 +392,135 @@ impl Node {
         }
     }
 
+    /// Emit errors for any modules beneath this node whose names collide with the names of the
+    /// generated types, which share their namespace.
+    fn check_collisions(&self, names: &Names) {
+        if let Ok(Children::Below(ref children)) = self.children {
+            for child in children {
+                if let Some(ref mod_name) = child.header.mod_name {
+                    if let Some((setting, _)) =
+                        names.all().into_iter().find(|(_, name)| *name == mod_name)
+                    {
+                        emit_error!(
+                            mod_name,
+                            "module `{}` collides with the name for `{}`",
+                            mod_name, setting;
+                            help = "rename the generated type with `#![schemata(names({} = \"...\"))]`", setting;
+                        );
+                    }
+
+                    // Segments are navigated to by methods of the same name on their parent's path
+                    let name = mod_name.unraw().to_string();
+                    if matches!(child.header.kind, Kind::Static { .. })
+                        && PATH_METHODS.contains(&name.as_str())
+                    {
+                        emit_error!(
+                            mod_name,
+                            "segment `{}` collides with the method `{}` of the generated paths",
+                            mod_name, name;
+                            help = "use a different module name for this segment, and `#[rename = \"{}\"]` to keep its key", name;
+                        );
+                    }
+                }
+                child.check_collisions(names);
+            }
+        }
+    }
+
+    /// Emit an error if there's a top-level segment named `root`, since its owned path function
+    /// `owned_root` would collide with the one for the root of the schema.
+    fn check_owned_root_collision(&self) {
+        let Ok(Children::Below(ref children)) = self.children else {
+            return;
+        };
+        for child in children {
+            let Some(ref mod_name) = child.header.mod_name else {
+                continue;
+            };
+            if matches!(child.header.kind, Kind::Static { .. }) && mod_name.unraw() == "root" {
+                emit_error!(
+                    mod_name,
+                    "top-level segment `root` collides with the function `owned_root` for the root of the schema";
+                    help = "use a different module name for this segment, and `#[rename = \"root\"]` to keep its key";
+                );
+            }
+        }
+    }
+
+    /// Emit errors for any leaves beneath this node which would be given the same method in the
+    /// generated visitor trait, and for any parameters which would collide with the `child` field
+    /// of the generated leaf enums.
+    fn check_visitor_collisions(&self, path: &mut Vec<Ident>, seen: &mut HashSet<String>) {
+        let Ok(Children::Below(ref children)) = self.children else {
+            return;
+        };
+
+        for child in children {
+            let mod_name = child
+                .header
+                .mod_name
+                .as_ref()
+                .expect("child always has a module name");
+
+            let is_static = match child.header.kind {
+                Kind::Static { .. } => true,
+                Kind::Var { .. } => {
+                    if mod_name == "child" && !child.segment_end().is_leaf() {
+                        emit_error!(
+                            mod_name,
+                            "parameter `child` collides with the field for the rest of the key in the generated `Leaf` enum";
+                            help = "use a different name for this parameter";
+                        );
+                    }
+                    false
+                }
+            };
+
+            if is_static {
+                path.push(mod_name.clone());
+            }
+
+            if let Ok(Children::Leaf(_)) = child.children {
+                let method = visitor_method_name(path);
+                if !seen.insert(method.to_string()) {
+                    emit_error!(
+                        mod_name,
+                        "leaf would have the same visitor method `{}` as another leaf",
+                        method;
+                        help = "use a different module name for one of them, and `#[rename = \"...\"]` to keep its key";
+                    );
+                }
+            }
+            child.check_visitor_collisions(path, seen);
+
+            if is_static {
+                path.pop();
+            }
+        }
+    }
+
+    /// The parameter nodes beneath this node in its segment, in order.
+    pub fn segment_params(&self) -> Vec<&Node> {
+        let mut params = Vec::new();
+        let mut current = self;
+        while let Ok(Children::Below(children)) = &current.children {
+            match children.as_slice() {
+                [child] if matches!(child.header.kind, Kind::Var { .. }) => {
+                    params.push(child);
+                    current = child;
+                }
+                _ => break,
+            }
+        }
+        params
+    }
+
+    /// The last node of the segment this node is part of: either its last parameter, or this node
+    /// itself if there are no parameters beneath it.
+    pub fn segment_end(&self) -> &Node {
+        self.segment_params().last().copied().unwrap_or(self)
+    }
+
     pub fn is_leaf(&self) -> bool {
         match &self.children {
             Err(_) => true,
@@ -168,20 +532,52 @@ impl Node {
     }
 }
 
+impl Header {
+    /// The name of this node's segment in an encoded key, if it is a static node other than the
+    /// root of the schema.
+    pub fn static_name(&self) -> Option<LitStr> {
+        match &self.kind {
+            Kind::Var { .. } => None,
+            Kind::Static {
+                renamed: Some(renamed),
+                ..
+            } => Some(renamed.clone()),
+            Kind::Static { renamed: None, .. } => self
+                .mod_name
+                .as_ref()
+                .map(|mod_name| LitStr::new(&mod_name.unraw().to_string(), mod_name.span())),
+        }
+    }
+}
+
+/// The name of the method of the generated visitor trait for the leaf at the end of the given path
+/// of segment names: the names joined with underscores.
+pub fn visitor_method_name(path: &[Ident]) -> Ident {
+    match path {
+        [name] => name.clone(),
+        _ => {
+            let joined = path
+                .iter()
+                .map(|name| name.unraw().to_string())
+                .collect::<Vec<_>>()
+                .join("_");
+            let span = path.last().map_or_else(Span::call_site, Ident::span);
+            Ident::new(&joined, span)
+        }
+    }
+}
+
 impl From<syntax::Syntax> for Ir {
     fn from(syntax::Syntax { attrs, children }: syntax::Syntax) -> Self {
-        // TODO: scrape settings from attrs
-        let settings = Settings {
-            names: Names::default(),
-            extensions: Extensions::default(), // TODO: scrape extensions based on enabled features
-        };
-        let docs = vec![]; // TODO: scrape docs from attrs
+        let settings = Settings::from_attrs(&attrs);
+        let docs = scrape_docs(&attrs);
 
         let children = Ok(Children::Below(
             children.into_iter().map(Node::from).collect(),
         ));
         let header = Header {
             docs,
+            vis: None,      // root node's visibility is part of the settings
             mod_name: None, // root node is only one not to have explicit mod name
             kind: Kind::Static {
                 renamed: None,
@@ -192,6 +588,9 @@ impl From<syntax::Syntax> for Ir {
 
         // We don't generate code beneath duplicated modules, so detect and prune it now
         root.prune_duplicates();
+        root.check_collisions(&settings.names);
+        root.check_owned_root_collision();
+        root.check_visitor_collisions(&mut Vec::new(), &mut HashSet::new());
 
         Ir { settings, root }
     }
@@ -223,20 +622,27 @@ impl From<syntax::Child> for Node {
             attrs, name, ty, ..
         }) = parameters.pop().map(Pair::into_value)
         {
-            let docs = vec![]; // TODO: scrape docs from attrs
+            let docs = scrape_docs(&attrs);
+            let by_value = scrape_by_value(&attrs) || is_small_copy(&ty);
             let header = Header {
                 docs,
+                vis: None,
                 mod_name: Some(*name),
-                kind: Kind::Var(ty),
+                kind: Kind::Var { ty, by_value },
             };
             children = Ok(Children::Below(vec![Node { header, children }]));
         }
 
         // Top off the result with a named static node
-        let docs = vec![]; // TODO: scrape docs from attrs
-        let renamed = None; // TODO: scrape rename from attrs
+        let docs = scrape_docs(&segment.attrs);
+        let renamed = scrape_rename(&segment.attrs);
+        let vis = match segment.vis {
+            Visibility::Inherited => None,
+            vis => Some(vis),
+        };
         let header = Header {
             docs,
+            vis,
             mod_name: Some(segment.name),
             kind: Kind::Static {
                 renamed,
@@ -247,3 +653,145 @@ impl From<syntax::Child> for Node {
         Node { header, children }
     }
 }
+
+/// Collect the lines of documentation (`///` or `//!` comments, or `#[doc = "..."]` attributes)
+/// among some attributes.
+fn scrape_docs(attrs: &[Attribute]) -> Vec<LitStr> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(doc), ..
+            })) => Some(doc),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Find the `#[by_value]` attribute among a parameter's attributes, emitting errors for malformed
+/// or repeated ones.
+fn scrape_by_value(attrs: &[Attribute]) -> bool {
+    let mut by_value = false;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("by_value")) {
+        if !attr.tokens.is_empty() {
+            emit_error!(
+                attr,
+                "malformed `by_value` attribute";
+                help = "mark the parameter with just `#[by_value]`";
+            );
+            continue;
+        }
+
+        if by_value {
+            emit_error!(attr, "parameter is marked `by_value` more than once");
+            continue;
+        }
+
+        by_value = true;
+    }
+
+    by_value
+}
+
+/// The largest size in bytes of a parameter which is passed by value without being asked to.
+const MAX_BY_VALUE_SIZE: usize = 32;
+
+/// Whether a parameter type is known to be small and `Copy`, so that it is worth passing by value
+/// without being asked to.
+fn is_small_copy(ty: &Type) -> bool {
+    copy_size(ty).is_some_and(|size| size <= MAX_BY_VALUE_SIZE)
+}
+
+/// The size in bytes of a type known to be `Copy`: a primitive number or `bool`, or an array (of
+/// literal length) or tuple of them. Padding is ignored, since this only needs to be roughly right.
+fn copy_size(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => {
+            const PRIMITIVES: &[(&str, usize)] = &[
+                ("u8", 1),
+                ("u16", 2),
+                ("u32", 4),
+                ("u64", 8),
+                ("u128", 16),
+                ("usize", 8),
+                ("i8", 1),
+                ("i16", 2),
+                ("i32", 4),
+                ("i64", 8),
+                ("i128", 16),
+                ("isize", 8),
+                ("f32", 4),
+                ("f64", 8),
+                ("bool", 1),
+            ];
+            let ident = path.get_ident()?;
+            PRIMITIVES
+                .iter()
+                .find(|(primitive, _)| ident == primitive)
+                .map(|&(_, size)| size)
+        }
+        Type::Array(TypeArray {
+            elem,
+            len: Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }),
+            ..
+        }) => copy_size(elem)?.checked_mul(len.base10_parse().ok()?),
+        Type::Tuple(TypeTuple { elems, .. }) => elems
+            .iter()
+            .try_fold(0usize, |total, elem| total.checked_add(copy_size(elem)?)),
+        Type::Paren(TypeParen { elem, .. }) | Type::Group(TypeGroup { elem, .. }) => {
+            copy_size(elem)
+        }
+        _ => None,
+    }
+}
+
+/// Find the `#[rename = "..."]` attribute among a segment's attributes, if there is one, emitting
+/// errors for malformed, repeated, or unencodable renames.
+fn scrape_rename(attrs: &[Attribute]) -> Option<LitStr> {
+    let mut renamed: Option<LitStr> = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("rename")) {
+        let name = match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(name),
+                ..
+            })) => name,
+            _ => {
+                emit_error!(
+                    attr,
+                    "malformed `rename` attribute";
+                    help = "specify the name to use in encoded keys, like `#[rename = \"name\"]`";
+                );
+                continue;
+            }
+        };
+
+        // An empty segment or one containing the separator could not be told apart from its
+        // neighbors when decoding a key
+        if name.value().is_empty() {
+            emit_error!(name, "path segment cannot be renamed to the empty string");
+            continue;
+        }
+        if name.value().contains('/') {
+            emit_error!(
+                name,
+                "path segment cannot contain the separator `/`";
+                help = "split this segment into nested segments instead";
+            );
+            continue;
+        }
+
+        if renamed.is_some() {
+            emit_error!(attr, "path segment is renamed more than once");
+            continue;
+        }
+
+        renamed = Some(name);
+    }
+
+    renamed
+}
@@ -5,6 +5,7 @@ use proc_macro_error::abort;
 use quote::quote;
 use syn::parse2;
 
+mod derive;
 mod generate;
 mod ir;
 mod syntax;
@@ -20,3 +21,11 @@ pub fn schema_internal(input: TokenStream) -> TokenStream {
         }
     }
 }
+
+#[doc(hidden)]
+pub fn derive_key_part(input: TokenStream) -> TokenStream {
+    match parse2::<syn::DeriveInput>(input) {
+        Err(err) => abort!(err),
+        Ok(input) => derive::key_part(input),
+    }
+}
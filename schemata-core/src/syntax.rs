@@ -1,9 +1,11 @@
+// Punctuation tokens are not read after parsing, but they are retained for their spans
+
 use syn::{
     braced, parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     token::{Brace, Colon, Comma, Paren, Semi},
-    Attribute, Ident, Result, Type,
+    Attribute, Ident, Result, Type, Visibility,
 };
 
 #[derive(Clone, Debug, Default)]
@@ -16,12 +18,15 @@ pub struct Syntax {
 pub enum Child {
     Leaf {
         segment: Segment,
+        #[allow(dead_code)]
         colon_token: Colon,
         ty: Box<Type>,
+        #[allow(dead_code)]
         semi_token: Semi,
     },
     Internal {
         segment: Segment,
+        #[allow(dead_code)]
         brace_token: Brace,
         children: Vec<Child>,
     },
@@ -30,12 +35,14 @@ pub enum Child {
 #[derive(Clone, Debug)]
 pub struct Segment {
     pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
     pub name: Ident,
     pub params: Option<Parameters>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Parameters {
+    #[allow(dead_code)]
     pub paren_token: Paren,
     pub params: Punctuated<Parameter, Comma>,
 }
@@ -44,6 +51,7 @@ pub struct Parameters {
 pub struct Parameter {
     pub attrs: Vec<Attribute>,
     pub name: Box<Ident>,
+    #[allow(dead_code)]
     pub colon_token: Colon,
     pub ty: Box<Type>,
 }
@@ -110,6 +118,7 @@ impl Parse for Segment {
     fn parse(input: ParseStream) -> Result<Self> {
         Ok(Segment {
             attrs: input.call(Attribute::parse_outer)?,
+            vis: input.parse()?,
             name: input.parse()?,
             params: {
                 let lookahead = input.lookahead1();
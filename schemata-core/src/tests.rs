@@ -1 +1,37 @@
 #![cfg(test)]
+
+use quote::quote;
+
+use crate::schema_internal;
+
+#[test]
+fn docs_and_templates() {
+    let output = schema_internal(quote! {
+        //! The schema.
+
+        /// Some floats.
+        baz(
+            /// The first float.
+            x: f32,
+            y: f32,
+        ) {
+            /// A leaf.
+            #[rename = "f"]
+            foo: u64;
+        }
+    })
+    .to_string()
+    // Doc comments in the input come through as raw strings, unlike the docs we generate
+    .replace("r\"", "\"");
+
+    for doc in [
+        r#"# [doc = " The schema."] # [derive"#,
+        r#"# [doc = " Some floats."] # [doc = ""] # [doc = " Prefix: `baz/`"] pub mod baz"#,
+        r#"# [doc = " The first float."] # [doc = ""] # [doc = " Prefix: `baz/{x}/`"] pub mod x"#,
+        r#"# [doc = " Prefix: `baz/{x}/{y}/`"] pub mod y"#,
+        r#"# [doc = " A leaf."] # [doc = ""] # [doc = " Key: `baz/{x}/{y}/f`"] pub mod foo"#,
+        r#"# [doc = " Some floats."] # [doc = ""] # [doc = " - `x`: The first float."] # [doc = " Prefix: `baz/{x}/{y}/`"] pub fn baz"#,
+    ] {
+        assert!(output.contains(doc), "missing {doc} in:\n{output}");
+    }
+}
@@ -9,3 +9,10 @@ use proc_macro_error::proc_macro_error;
 pub fn schema_internal(input: TokenStream) -> TokenStream {
     schemata_core::schema_internal(input.into()).into()
 }
+
+#[doc(hidden)]
+#[proc_macro_error]
+#[proc_macro_derive(KeyPart)]
+pub fn derive_key_part(input: TokenStream) -> TokenStream {
+    schemata_core::derive_key_part(input.into()).into()
+}
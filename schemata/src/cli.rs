@@ -0,0 +1,333 @@
+//! A command-line interface for inspecting and modifying a store, generated from its schema.
+//!
+//! A schema with the `#![schemata(cli)]` setting can be used with [`Command`], which parses the
+//! subcommands `get`, `put`, `delete` and `list`, followed by the subcommands and arguments for a
+//! key (or for `list`, a prefix) in the schema, and then runs them against any [`Backend`]:
+//!
+//! ```
+//! use schemata::{clap, cli::Command, schema, MemoryStore};
+//!
+//! schema! {
+//!     #![schemata(cli)]
+//!
+//!     height: u64;
+//!     account(id: u32) {
+//!         balance: u64;
+//!     }
+//! }
+//!
+//! #[derive(clap::Parser)]
+//! struct Options {
+//!     #[command(subcommand)]
+//!     command: Command<Schema>,
+//! }
+//!
+//! fn main() {
+//!     let mut store = MemoryStore::new();
+//!     let mut output = Vec::new();
+//!     for args in [
+//!         ["store", "put", "account", "--id", "7", "balance", "100"].as_slice(),
+//!         &["store", "get", "account", "--id", "7", "balance"],
+//!     ] {
+//!         let options = <Options as clap::Parser>::parse_from(args);
+//!         options.command.run(&mut store, &mut output).unwrap();
+//!     }
+//!     assert_eq!(output, b"100\n");
+//! }
+//! ```
+
+use std::{error::Error, fmt, io};
+
+use clap::{error::ErrorKind, Arg, ArgMatches, Args, FromArgMatches, Subcommand};
+
+use crate::{Backend, Decode, Encode, Order, Schema, StoreError, Value, ValueError};
+
+/// A value which can be read from and written as text on the command line.
+///
+/// The value type of every leaf of a schema with the `#![schemata(cli)]` setting must implement
+/// this trait.
+pub trait TextValue: Value {
+    /// Parse a value from text.
+    fn from_text(text: &str) -> Result<Self, ValueError>;
+
+    /// Write a value as text, such that [`TextValue::from_text`] would parse it back again.
+    fn to_text(&self) -> String;
+}
+
+macro_rules! text_value_from_str {
+    ($($ty:ty),*) => {
+        $(
+            impl TextValue for $ty {
+                fn from_text(text: &str) -> Result<Self, ValueError> {
+                    text.parse().map_err(ValueError::new)
+                }
+
+                fn to_text(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+text_value_from_str!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64, bool, String);
+
+/// Bytes are written as hexadecimal.
+impl TextValue for Vec<u8> {
+    fn from_text(text: &str) -> Result<Self, ValueError> {
+        if !text.len().is_multiple_of(2) {
+            return Err(ValueError::new("odd number of hexadecimal digits"));
+        }
+        // Work on bytes rather than slicing the string, which could split a multi-byte character
+        let digit = |byte: u8| {
+            char::from(byte)
+                .to_digit(16)
+                .ok_or_else(|| ValueError::new(format!("invalid hexadecimal digit in `{text}`")))
+        };
+        text.as_bytes()
+            .chunks(2)
+            .map(|pair| Ok((digit(pair[0])? * 16 + digit(pair[1])?) as u8))
+            .collect()
+    }
+
+    fn to_text(&self) -> String {
+        self.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+/// The unit value is written as the empty string.
+impl TextValue for () {
+    fn from_text(text: &str) -> Result<Self, ValueError> {
+        if text.is_empty() {
+            Ok(())
+        } else {
+            Err(ValueError::new("expected empty value"))
+        }
+    }
+
+    fn to_text(&self) -> String {
+        String::new()
+    }
+}
+
+/// A key for any leaf of a schema, whose value can be converted to and from text.
+///
+/// This is implemented by the `OwnedKey` at the root of every schema with the
+/// `#![schemata(cli)]` setting, by dispatching to the [`TextValue`] implementation of the value
+/// type of the leaf the key is for.
+pub trait TextKey {
+    /// Parse the value for this key from text, and encode it.
+    fn value_from_text(&self, text: &str) -> Result<Vec<u8>, ValueError>;
+
+    /// Decode the value for this key, and write it as text.
+    fn value_to_text(&self, bytes: &[u8]) -> Result<String, ValueError>;
+}
+
+/// A command to run against a store with the schema `S`.
+///
+/// This can be used as a [`clap`] subcommand, or flattened into a larger command-line interface.
+pub enum Command<S: Schema> {
+    /// Get the value stored under a key.
+    Get(S::OwnedKey),
+    /// Store a value, written as text, under a key.
+    Put(S::OwnedKey, String),
+    /// Delete the value stored under a key.
+    Delete(S::OwnedKey),
+    /// List all the entries beneath a prefix.
+    List(S::OwnedPrefix),
+}
+
+impl<S: Schema> Command<S>
+where
+    S::OwnedKey: TextKey + fmt::Display,
+{
+    /// Run this command against a backend, writing its output to `out`.
+    ///
+    /// Values are written one per line, and listed entries as the key and its value separated by a
+    /// tab, with the key written as its path, like `account/7/balance`.
+    ///
+    /// Listing stops at the first entry whose key or value can't be decoded, with a
+    /// [`StoreError::Key`] or [`StoreError::Value`] error, after writing the entries before it.
+    pub fn run<B: Backend + ?Sized>(
+        &self,
+        backend: &mut B,
+        out: &mut impl io::Write,
+    ) -> Result<(), CliError<B::Error>> {
+        match self {
+            Command::Get(key) => {
+                let bytes = backend
+                    .get_raw(&key.to_bytes())
+                    .map_err(StoreError::Backend)?
+                    .ok_or(CliError::NotFound)?;
+                let text = key.value_to_text(&bytes).map_err(StoreError::Value)?;
+                writeln!(out, "{text}")?;
+            }
+            Command::Put(key, text) => {
+                let bytes = key.value_from_text(text).map_err(StoreError::Value)?;
+                backend
+                    .put_raw(key.to_bytes(), bytes)
+                    .map_err(StoreError::Backend)?;
+            }
+            Command::Delete(key) => {
+                backend
+                    .delete_raw(&key.to_bytes())
+                    .map_err(StoreError::Backend)?;
+            }
+            Command::List(prefix) => {
+                for entry in backend.scan_raw(&prefix.to_bytes(), None, Order::Ascending) {
+                    let (bytes, value) = entry.map_err(StoreError::Backend)?;
+                    let key = S::OwnedKey::from_bytes(&bytes).map_err(StoreError::Key)?;
+                    let text = key.value_to_text(&value).map_err(StoreError::Value)?;
+                    writeln!(out, "{key}\t{text}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The identifier of the argument for the value of a `put` command, chosen so as not to collide
+/// with any parameter in the schema.
+const VALUE: &str = "schemata-value";
+
+impl<S: Schema> FromArgMatches for Command<S>
+where
+    S::OwnedKey: Args,
+    S::OwnedPrefix: Args,
+{
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        match matches.subcommand() {
+            Some(("get", matches)) => Ok(Command::Get(S::OwnedKey::from_arg_matches(matches)?)),
+            Some(("put", matches)) => {
+                let key = S::OwnedKey::from_arg_matches(matches)?;
+
+                // The value is given after the key, so it belongs to the innermost subcommand
+                let mut leaf = matches;
+                while let Some((_, matches)) = leaf.subcommand() {
+                    leaf = matches;
+                }
+                let value = leaf.get_one::<String>(VALUE).cloned().ok_or_else(|| {
+                    clap::Error::raw(
+                        ErrorKind::MissingRequiredArgument,
+                        "a value is required to put\n",
+                    )
+                })?;
+
+                Ok(Command::Put(key, value))
+            }
+            Some(("delete", matches)) => {
+                Ok(Command::Delete(S::OwnedKey::from_arg_matches(matches)?))
+            }
+            Some(("list", matches)) => {
+                Ok(Command::List(S::OwnedPrefix::from_arg_matches(matches)?))
+            }
+            Some((name, _)) => Err(clap::Error::raw(
+                ErrorKind::InvalidSubcommand,
+                format!("unrecognized subcommand '{name}'\n"),
+            )),
+            None => Err(clap::Error::raw(
+                ErrorKind::MissingSubcommand,
+                "a subcommand is required\n",
+            )),
+        }
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl<S: Schema> Subcommand for Command<S>
+where
+    S::OwnedKey: Args,
+    S::OwnedPrefix: Args,
+{
+    fn augment_subcommands(command: clap::Command) -> clap::Command {
+        command
+            .subcommand(S::OwnedKey::augment_args(
+                clap::Command::new("get").about("Get the value stored under a key"),
+            ))
+            .subcommand(with_value_arg(S::OwnedKey::augment_args(
+                clap::Command::new("put").about("Store a value under a key"),
+            )))
+            .subcommand(S::OwnedKey::augment_args(
+                clap::Command::new("delete").about("Delete the value stored under a key"),
+            ))
+            .subcommand(S::OwnedPrefix::augment_args(
+                clap::Command::new("list").about("List all the entries beneath a prefix"),
+            ))
+    }
+
+    fn augment_subcommands_for_update(command: clap::Command) -> clap::Command {
+        Self::augment_subcommands(command)
+    }
+
+    fn has_subcommand(name: &str) -> bool {
+        matches!(name, "get" | "put" | "delete" | "list")
+    }
+}
+
+/// Add an argument for the value to every innermost subcommand, i.e. those which complete a key.
+fn with_value_arg(command: clap::Command) -> clap::Command {
+    if command.has_subcommands() {
+        let names: Vec<String> = command
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_owned())
+            .collect();
+        names.into_iter().fold(command, |command, name| {
+            command.mut_subcommand(name, with_value_arg)
+        })
+    } else {
+        command.arg(
+            Arg::new(VALUE)
+                .value_name("VALUE")
+                .required(true)
+                .allow_negative_numbers(true)
+                .help("The value to store, as text"),
+        )
+    }
+}
+
+/// An error encountered while running a [`Command`].
+#[derive(Debug)]
+pub enum CliError<E> {
+    /// There was no value stored under the key.
+    NotFound,
+    /// Using the store failed.
+    Store(StoreError<E>),
+    /// Writing the output failed.
+    Io(io::Error),
+}
+
+impl<E> From<StoreError<E>> for CliError<E> {
+    fn from(error: StoreError<E>) -> Self {
+        CliError::Store(error)
+    }
+}
+
+impl<E> From<io::Error> for CliError<E> {
+    fn from(error: io::Error) -> Self {
+        CliError::Io(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for CliError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NotFound => write!(f, "no value is stored under this key"),
+            CliError::Store(error) => write!(f, "{error}"),
+            CliError::Io(error) => write!(f, "failed to write output: {error}"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CliError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CliError::NotFound => None,
+            CliError::Store(error) => Some(error),
+            CliError::Io(error) => Some(error),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use crate::{
+    encode::{ESCAPE, TERMINATOR, ZERO},
+    SEPARATOR,
+};
+
+/// A type which can be decoded from (part of) a key in a key-value store.
+///
+/// Every `OwnedKey` generated by [`schema!`](crate::schema) implements this trait, as does the
+/// owned form of every [`KeyPart`](crate::KeyPart) which can be a parameter in a schema.
+pub trait Decode: Sized {
+    /// Decode a value from the start of `input`, advancing `input` past the decoded bytes.
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError>;
+
+    /// Decode a value from exactly the bytes given, failing if any are left over.
+    fn from_bytes(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        let value = Self::decode(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes { len: bytes.len() });
+        }
+        Ok(value)
+    }
+}
+
+/// An error encountered while decoding a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input ended before a complete key was decoded.
+    UnexpectedEnd,
+    /// A complete key was decoded, but some bytes were left over after it.
+    TrailingBytes {
+        /// The number of bytes left over.
+        len: usize,
+    },
+    /// The input did not start with any of the segments which could come next in the schema.
+    NoMatchingSegment {
+        /// The segments which could have come next.
+        expected: &'static [&'static str],
+    },
+    /// The input did not contain a separator between two segments.
+    MissingSeparator,
+    /// The input was a valid key, but for a different leaf of the schema than the one expected.
+    DifferentLeaf,
+    /// The bytes of a parameter were not a valid encoding of its type.
+    InvalidParam {
+        /// The name of the type of the parameter.
+        ty: &'static str,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of key"),
+            DecodeError::TrailingBytes { len } => {
+                write!(f, "{len} unexpected trailing byte(s) after key")
+            }
+            DecodeError::NoMatchingSegment { expected } => {
+                write!(f, "no matching segment in key, expected one of: ")?;
+                for (i, segment) in expected.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{segment:?}")?;
+                }
+                Ok(())
+            }
+            DecodeError::MissingSeparator => write!(f, "missing separator between segments"),
+            DecodeError::DifferentLeaf => write!(f, "key is for a different leaf of the schema"),
+            DecodeError::InvalidParam { ty } => write!(f, "invalid encoding of `{ty}` in key"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Strip the separator between two segments from the start of `input`.
+///
+/// This is used by generated code, and is not part of the public API.
+#[doc(hidden)]
+pub fn decode_separator(input: &mut &[u8]) -> Result<(), DecodeError> {
+    match input.split_first() {
+        Some((&SEPARATOR, rest)) => {
+            *input = rest;
+            Ok(())
+        }
+        Some(_) => Err(DecodeError::MissingSeparator),
+        None => Err(DecodeError::UnexpectedEnd),
+    }
+}
+
+/// Strip the static segment `[name]` from the start of `input`, making sure that it isn't merely
+/// the start of some longer segment.
+///
+/// This is used by generated code, and is not part of the public API.
+#[doc(hidden)]
+pub fn decode_segment(
+    input: &mut &[u8],
+    segment: &'static [&'static str; 1],
+) -> Result<(), DecodeError> {
+    let [name] = segment;
+    match input.strip_prefix(name.as_bytes()) {
+        Some(rest) if rest.is_empty() || rest[0] == SEPARATOR => {
+            *input = rest;
+            Ok(())
+        }
+        _ => Err(DecodeError::NoMatchingSegment { expected: segment }),
+    }
+}
+
+fn take<const N: usize>(input: &mut &[u8]) -> Result<[u8; N], DecodeError> {
+    if input.len() < N {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (bytes, rest) = input.split_at(N);
+    *input = rest;
+    Ok(bytes.try_into().expect("slice has exactly N bytes"))
+}
+
+// These are the inverses of the order-preserving encodings in `encode`
+
+macro_rules! decode_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl Decode for $ty {
+                fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                    take(input).map(<$ty>::from_be_bytes)
+                }
+            }
+        )*
+    };
+}
+
+decode_unsigned!(u8, u16, u32, u64, u128);
+
+macro_rules! decode_signed {
+    ($($ty:ty => $unsigned:ty),*) => {
+        $(
+            impl Decode for $ty {
+                fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                    let flipped = <$unsigned>::decode(input)?;
+                    Ok((flipped ^ (1 << (<$unsigned>::BITS - 1))) as $ty)
+                }
+            }
+        )*
+    };
+}
+
+decode_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+macro_rules! decode_float {
+    ($($ty:ty => $bits:ty),*) => {
+        $(
+            impl Decode for $ty {
+                fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                    let flipped = <$bits>::decode(input)?;
+                    let sign: $bits = 1 << (<$bits>::BITS - 1);
+                    let bits = if flipped & sign != 0 { flipped ^ sign } else { !flipped };
+                    Ok(<$ty>::from_bits(bits))
+                }
+            }
+        )*
+    };
+}
+
+decode_float!(f32 => u32, f64 => u64);
+
+impl Decode for usize {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        u64::decode(input)?
+            .try_into()
+            .map_err(|_| DecodeError::InvalidParam { ty: "usize" })
+    }
+}
+
+impl Decode for isize {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        i64::decode(input)?
+            .try_into()
+            .map_err(|_| DecodeError::InvalidParam { ty: "isize" })
+    }
+}
+
+impl Decode for bool {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take(input)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(DecodeError::InvalidParam { ty: "bool" }),
+        }
+    }
+}
+
+impl Decode for Vec<u8> {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let mut bytes = Vec::new();
+        loop {
+            let [byte] = take(input)?;
+            if byte != ZERO {
+                bytes.push(byte);
+                continue;
+            }
+            match take(input)? {
+                [ESCAPE] => bytes.push(ZERO),
+                [TERMINATOR] => return Ok(bytes),
+                _ => return Err(DecodeError::InvalidParam { ty: "Vec<u8>" }),
+            }
+        }
+    }
+}
+
+impl Decode for String {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        String::from_utf8(Vec::decode(input)?)
+            .map_err(|_| DecodeError::InvalidParam { ty: "String" })
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let elements = (0..N)
+            .map(|_| T::decode(input))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(elements
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("exactly N elements were decoded")))
+    }
+}
+
+macro_rules! decode_tuple {
+    ($(($($name:ident),*)),*) => {
+        $(
+            impl<$($name: Decode),*> Decode for ($($name,)*) {
+                fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                    Ok(($($name::decode(input)?,)*))
+                }
+            }
+        )*
+    };
+}
+
+decode_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F)
+);
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take(input)? {
+            [0] => Ok(None),
+            [1] => T::decode(input).map(Some),
+            _ => Err(DecodeError::InvalidParam { ty: "Option" }),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+/// The byte which separates the segments of an encoded key or prefix.
+pub const SEPARATOR: u8 = b'/';
+
+/// A type which can be encoded as (part of) a key in a key-value store.
+///
+/// Every `Key` and `Prefix` generated by [`schema!`](crate::schema) implements this trait, as does
+/// every [`KeyPart`](crate::KeyPart) which can be a parameter in a schema.
+pub trait Encode {
+    /// Append the encoding of this value to the end of `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Encode this value into a fresh vector of bytes.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf);
+        buf
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (**self).encode(buf)
+    }
+}
+
+// Parameters are encoded so that the lexicographic order of their encodings matches the order of
+// their values, so that iterating over a store visits keys in order of their parameters
+
+macro_rules! encode_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+encode_unsigned!(u8, u16, u32, u64, u128);
+
+// Flipping the sign bit of a two's complement integer moves the negative numbers below the
+// positive ones, after which the usual big-endian order is correct
+macro_rules! encode_signed {
+    ($($ty:ty => $unsigned:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    let flipped = (*self as $unsigned) ^ (1 << (<$unsigned>::BITS - 1));
+                    flipped.encode(buf)
+                }
+            }
+        )*
+    };
+}
+
+encode_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+// Floats are encoded in the IEEE 754 total order: flipping the sign bit of positive numbers moves
+// them above the negative ones, and flipping all the bits of negative numbers reverses their order
+macro_rules! encode_float {
+    ($($ty:ty => $bits:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    let bits = self.to_bits();
+                    let sign: $bits = 1 << (<$bits>::BITS - 1);
+                    let flipped = if bits & sign == 0 { bits ^ sign } else { !bits };
+                    flipped.encode(buf)
+                }
+            }
+        )*
+    };
+}
+
+encode_float!(f32 => u32, f64 => u64);
+
+// Pointer-sized integers are encoded as 64 bits wide, so keys don't depend on the platform
+impl Encode for usize {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as u64).encode(buf)
+    }
+}
+
+impl Encode for isize {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as i64).encode(buf)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+}
+
+// Byte strings can be any length, so they need a terminator: a zero byte followed by `0x01` ends
+// the string, and zero bytes within it are escaped as a zero followed by `0xFF`. A zero is always
+// followed by one of those two, so the end of a string can't be mistaken for an escaped zero
+// whatever comes after it, and since the terminator sorts below every escaped zero and every other
+// byte, a string sorts before any longer string it is the start of.
+pub(crate) const ZERO: u8 = 0x00;
+pub(crate) const TERMINATOR: u8 = 0x01;
+pub(crate) const ESCAPE: u8 = 0xFF;
+
+impl Encode for [u8] {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for &byte in self {
+            buf.push(byte);
+            if byte == ZERO {
+                buf.push(ESCAPE);
+            }
+        }
+        buf.extend_from_slice(&[ZERO, TERMINATOR]);
+    }
+}
+
+impl Encode for Vec<u8> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_slice().encode(buf)
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().encode(buf)
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().encode(buf)
+    }
+}
+
+// Arrays and tuples are encoded as each of their elements in turn, which preserves their
+// lexicographic order because each element's encoding is self-delimiting
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for element in self {
+            element.encode(buf);
+        }
+    }
+}
+
+macro_rules! encode_tuple {
+    ($(($($name:ident),*)),*) => {
+        $(
+            impl<$($name: Encode),*> Encode for ($($name,)*) {
+                #[allow(non_snake_case)]
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    let ($($name,)*) = self;
+                    $($name.encode(buf);)*
+                }
+            }
+        )*
+    };
+}
+
+encode_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F)
+);
+
+// `None` sorts before every `Some`, as it does for `Option`'s own order
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                value.encode(buf);
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::{borrow::Borrow, fmt};
+
+use crate::{Decode, Encode};
+
+/// A type which can be a parameter in a schema, encoded as one segment of its keys.
+///
+/// The type of every parameter in a [`schema!`](crate::schema) must implement this trait. Its
+/// encoding must uphold two guarantees, so that keys can be decoded and are stored in a sensible
+/// order:
+///
+/// - It is self-delimiting: no encoding is the start of another, longer one, so that a parameter
+///   can be decoded from the start of the rest of a key without knowing where it ends.
+/// - It preserves order: the encodings of two values sort lexicographically in the same order as
+///   the values themselves (for floats, in their total order), so that iterating over a store
+///   visits keys in order of their parameters.
+///
+/// This is implemented for integers, floats, `bool`, strings, byte strings, and arrays, tuples and
+/// `Option`s of other key parts.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as a parameter in a schema",
+    label = "this type does not implement `KeyPart`",
+    note = "parameter types must implement `schemata::KeyPart`, which describes how they are encoded in keys"
+)]
+pub trait KeyPart: Encode + fmt::Debug + ToOwned<Owned = <Self as KeyPart>::Owned> {
+    /// The owned form of this type, which is what is decoded from a key: the type itself for sized
+    /// types, or its owned counterpart for unsized ones, like `String` for `str`.
+    ///
+    /// Borrowed paths hold a `&Self` (or a `Self`, for small `Copy` types passed by value), and
+    /// owned paths hold one of these, so this must be the same as [`ToOwned::Owned`].
+    type Owned: KeyPart<Owned = <Self as KeyPart>::Owned> + Decode + Clone + Borrow<Self>;
+
+    /// The length in bytes of every encoding of this type, if they are all the same length.
+    const FIXED_LEN: Option<usize> = None;
+}
+
+macro_rules! key_part_fixed {
+    ($($ty:ty => $len:expr),*) => {
+        $(
+            impl KeyPart for $ty {
+                type Owned = $ty;
+                const FIXED_LEN: Option<usize> = Some($len);
+            }
+        )*
+    };
+}
+
+key_part_fixed!(
+    u8 => 1, u16 => 2, u32 => 4, u64 => 8, u128 => 16,
+    i8 => 1, i16 => 2, i32 => 4, i64 => 8, i128 => 16,
+    f32 => 4, f64 => 8, usize => 8, isize => 8, bool => 1
+);
+
+impl KeyPart for str {
+    type Owned = String;
+}
+
+impl KeyPart for String {
+    type Owned = String;
+}
+
+impl KeyPart for [u8] {
+    type Owned = Vec<u8>;
+}
+
+impl KeyPart for Vec<u8> {
+    type Owned = Vec<u8>;
+}
+
+// The elements of arrays, tuples and options must be sized and decodable themselves
+
+impl<T: KeyPart<Owned = T> + Decode + Clone, const N: usize> KeyPart for [T; N] {
+    type Owned = [T; N];
+    const FIXED_LEN: Option<usize> = match T::FIXED_LEN {
+        Some(len) => Some(len * N),
+        None => None,
+    };
+}
+
+/// The total of some lengths, if all of them are fixed.
+pub const fn sum_fixed_len(lens: &[Option<usize>]) -> Option<usize> {
+    let mut total = 0;
+    let mut i = 0;
+    while i < lens.len() {
+        match lens[i] {
+            Some(len) => total += len,
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(total)
+}
+
+/// The length shared by all of some lengths, if they are all fixed and the same.
+pub const fn same_fixed_len(lens: &[Option<usize>]) -> Option<usize> {
+    let Some(first) = (match lens.first() {
+        Some(first) => *first,
+        None => None,
+    }) else {
+        return None;
+    };
+    let mut i = 1;
+    while i < lens.len() {
+        match lens[i] {
+            Some(len) if len == first => {}
+            _ => return None,
+        }
+        i += 1;
+    }
+    Some(first)
+}
+
+macro_rules! key_part_tuple {
+    ($(($($name:ident),*)),*) => {
+        $(
+            impl<$($name: KeyPart<Owned = $name> + Decode + Clone),*> KeyPart for ($($name,)*) {
+                type Owned = ($($name,)*);
+                const FIXED_LEN: Option<usize> = sum_fixed_len(&[$($name::FIXED_LEN),*]);
+            }
+        )*
+    };
+}
+
+key_part_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F)
+);
+
+impl<T: KeyPart<Owned = T> + Decode + Clone> KeyPart for Option<T> {
+    type Owned = Option<T>;
+}
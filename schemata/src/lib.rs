@@ -1 +1,77 @@
 pub use schemata_macro::schema_internal as schema;
+
+#[cfg(feature = "clap")]
+pub mod cli;
+mod decode;
+mod encode;
+mod key_part;
+mod memory;
+mod store;
+mod traits;
+mod value;
+#[doc(hidden)]
+pub use decode::{decode_segment, decode_separator};
+pub use decode::{Decode, DecodeError};
+pub use encode::{Encode, SEPARATOR};
+pub use key_part::KeyPart;
+pub use memory::{Entries, MemoryStore};
+/// Derive [`KeyPart`], along with [`Encode`] and [`Decode`], for a struct or enum, so that it can be
+/// a parameter in a schema.
+///
+/// A struct is encoded as each of its fields in turn, and an enum as the index of its variant
+/// followed by each of that variant's fields, so that the encodings sort in the same order as
+/// `#[derive(PartialOrd, Ord)]` would order the values (for enums, as long as their variants don't
+/// have explicit discriminants). The type must also implement `Clone` and `Debug`, and every field
+/// must be a [`KeyPart`] itself.
+///
+/// ```
+/// use schemata::{schema, KeyPart};
+///
+/// #[derive(Clone, Copy, Debug, KeyPart)]
+/// pub struct AssetId([u8; 4]);
+///
+/// #[derive(Clone, Debug, KeyPart)]
+/// pub enum PoolKind {
+///     Stable,
+///     Weighted { weight: u8 },
+/// }
+///
+/// schema! {
+///     pool(kind: PoolKind, asset: AssetId) {
+///         reserves: u64;
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub use schemata_macro::KeyPart;
+pub use store::{Backend, Order, Scan, Store, StoreError};
+pub use traits::{Key, OwnedKey, OwnedParams, Params, Path, Prefix, Schema};
+pub use value::{Value, ValueError};
+
+/// The version of `clap` used by schemas with the `#![schemata(clap)]` setting, which makes their
+/// owned keys parseable as command-line arguments.
+#[cfg(feature = "clap")]
+pub use clap;
+
+/// Items used by generated code, which are not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    /// Glob-imported into the modules of schemas using `clap`, so this must hold nothing else.
+    #[cfg(feature = "clap")]
+    pub mod clap_scope {
+        pub use clap;
+    }
+
+    /// Require that the type of a parameter is a [`KeyPart`](crate::KeyPart), so that a bad type
+    /// is reported where it is written in the schema.
+    pub fn assert_key_part<T: crate::KeyPart + ?Sized>() {}
+
+    /// Require that the type of a sized parameter is a [`KeyPart`](crate::KeyPart) which is its
+    /// own owned form, as the generated code assumes.
+    pub fn assert_sized_key_part<T: crate::KeyPart<Owned = T>>() {}
+
+    /// Require that the type of a parameter passed by value is `Copy`.
+    pub fn assert_copy<T: Copy>() {}
+
+    pub use crate::key_part::{same_fixed_len, sum_fixed_len};
+}
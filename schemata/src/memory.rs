@@ -0,0 +1,129 @@
+use std::{
+    collections::{btree_map, BTreeMap},
+    convert::Infallible,
+    ops::Bound,
+};
+
+use crate::{Backend, Order};
+
+/// An in-memory [`Backend`] backed by an ordered map, useful for testing.
+///
+/// Since it is ordered, iteration over a `MemoryStore` is deterministic, and two stores can be
+/// compared for equality, or cloned to take a snapshot of their state.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MemoryStore {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryStore {
+    /// Create a new, empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of entries in the store.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the store has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over all the raw entries in the store, in ascending order of key.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+    }
+}
+
+impl Backend for MemoryStore {
+    type Error = Infallible;
+    type Entries<'a> = Entries<'a>;
+
+    fn get_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Infallible> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn put_raw(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), Infallible> {
+        self.entries.insert(key, value);
+        Ok(())
+    }
+
+    fn delete_raw(&mut self, key: &[u8]) -> Result<(), Infallible> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn scan_raw(&self, prefix: &[u8], start_after: Option<&[u8]>, order: Order) -> Entries<'_> {
+        // Every key beginning with the prefix lies between the prefix itself and its successor
+        let mut lower = Bound::Included(prefix.to_vec());
+        let mut upper = match successor(prefix) {
+            Some(successor) => Bound::Excluded(successor),
+            None => Bound::Unbounded,
+        };
+
+        // Narrow that range down to start after the cursor, if it lies within it
+        if let Some(start_after) = start_after {
+            match order {
+                Order::Ascending if start_after >= prefix => {
+                    lower = Bound::Excluded(start_after.to_vec())
+                }
+                Order::Descending if !matches!(&upper, Bound::Excluded(upper) if start_after >= upper) => {
+                    upper = Bound::Excluded(start_after.to_vec())
+                }
+                _ => {}
+            }
+        }
+
+        // The map panics rather than returning nothing if the range is empty or backwards
+        let empty = match (&lower, &upper) {
+            (Bound::Included(lower) | Bound::Excluded(lower), Bound::Excluded(upper)) => {
+                lower >= upper
+            }
+            _ => false,
+        };
+        let range = if empty {
+            self.entries
+                .range::<[u8], _>((Bound::Included(prefix), Bound::Excluded(prefix)))
+        } else {
+            self.entries.range((lower, upper))
+        };
+
+        Entries { range, order }
+    }
+}
+
+/// The least byte string which is greater than every byte string beginning with `prefix`, if
+/// there is one.
+fn successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    while let Some(last) = successor.pop() {
+        if last < u8::MAX {
+            successor.push(last + 1);
+            return Some(successor);
+        }
+    }
+    None
+}
+
+/// An iterator over the raw entries of a [`MemoryStore`] beneath some prefix.
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+    range: btree_map::Range<'a, Vec<u8>, Vec<u8>>,
+    order: Order,
+}
+
+impl Iterator for Entries<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>), Infallible>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = match self.order {
+            Order::Ascending => self.range.next()?,
+            Order::Descending => self.range.next_back()?,
+        };
+        Some(Ok((key.clone(), value.clone())))
+    }
+}
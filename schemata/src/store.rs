@@ -0,0 +1,180 @@
+use std::{error::Error, fmt, marker::PhantomData};
+
+use crate::{DecodeError, Encode, Key, OwnedKey, Prefix, Value, ValueError};
+
+/// A raw key-value store, mapping bytes to bytes, which can be used as a [`Store`].
+pub trait Backend {
+    /// The error produced by this backend.
+    type Error;
+
+    /// An iterator over the raw entries of this backend.
+    type Entries<'a>: Iterator<Item = Result<(Vec<u8>, Vec<u8>), Self::Error>>
+    where
+        Self: 'a;
+
+    /// Get the value stored under a key, if any.
+    fn get_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Store a value under a key, replacing any previous value.
+    fn put_raw(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), Self::Error>;
+
+    /// Delete the value stored under a key, if any.
+    fn delete_raw(&mut self, key: &[u8]) -> Result<(), Self::Error>;
+
+    /// Iterate over all the entries whose keys start with `prefix`, in the given order of key.
+    ///
+    /// If `start_after` is given, only entries whose keys come strictly after it in that order are
+    /// included.
+    fn scan_raw(
+        &self,
+        prefix: &[u8],
+        start_after: Option<&[u8]>,
+        order: Order,
+    ) -> Self::Entries<'_>;
+}
+
+/// The order in which to iterate over the entries of a store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// In ascending order of key.
+    #[default]
+    Ascending,
+    /// In descending order of key.
+    Descending,
+}
+
+/// A typed key-value store, storing values of the type declared in the schema for each key.
+///
+/// This is implemented for every [`Backend`], and is the only place where keys and values are
+/// converted to and from bytes.
+pub trait Store: Backend {
+    /// Get the value stored under a key, if any.
+    fn get<K: Key>(&self, key: &K) -> Result<Option<K::Value>, StoreError<Self::Error>>
+    where
+        K::Value: Value,
+    {
+        match self.get_raw(&key.to_bytes()).map_err(StoreError::Backend)? {
+            Some(bytes) => Ok(Some(K::Value::decode_value(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a value under a key, replacing any previous value.
+    fn put<K: Key>(&mut self, key: &K, value: &K::Value) -> Result<(), StoreError<Self::Error>>
+    where
+        K::Value: Value,
+    {
+        let mut bytes = Vec::new();
+        value.encode_value(&mut bytes);
+        self.put_raw(key.to_bytes(), bytes)
+            .map_err(StoreError::Backend)
+    }
+
+    /// Delete the value stored under a key, if any.
+    fn delete<K: Key>(&mut self, key: &K) -> Result<(), StoreError<Self::Error>> {
+        self.delete_raw(&key.to_bytes())
+            .map_err(StoreError::Backend)
+    }
+
+    /// Iterate over all the entries for the leaf `K` beneath a prefix, in ascending order of key.
+    ///
+    /// Entries beneath the prefix which belong to other leaves of the schema are skipped.
+    fn scan<K: OwnedKey>(&self, prefix: &impl Prefix<Schema = K::Schema>) -> Scan<'_, Self, K>
+    where
+        K::Value: Value,
+    {
+        self.scan_from(prefix, None, Order::Ascending)
+    }
+
+    /// Iterate over all the entries for the leaf `K` beneath a prefix, in the given order of key,
+    /// starting after the key `start_after` if one is given.
+    ///
+    /// Entries beneath the prefix which belong to other leaves of the schema are skipped.
+    fn scan_from<K: OwnedKey>(
+        &self,
+        prefix: &impl Prefix<Schema = K::Schema>,
+        start_after: Option<&K>,
+        order: Order,
+    ) -> Scan<'_, Self, K>
+    where
+        K::Value: Value,
+    {
+        let start_after = start_after.map(Encode::to_bytes);
+        Scan {
+            entries: self.scan_raw(&prefix.to_bytes(), start_after.as_deref(), order),
+            key: PhantomData,
+        }
+    }
+}
+
+impl<B: Backend + ?Sized> Store for B {}
+
+/// An iterator over the typed entries for a leaf of a schema in a [`Store`], returned by
+/// [`Store::scan`] and [`Store::scan_from`].
+pub struct Scan<'a, B: Backend + ?Sized + 'a, K> {
+    entries: B::Entries<'a>,
+    key: PhantomData<fn() -> K>,
+}
+
+impl<B: Backend + ?Sized, K: OwnedKey> Iterator for Scan<'_, B, K>
+where
+    K::Value: Value,
+{
+    type Item = Result<(K, K::Value), StoreError<B::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key, value) = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(StoreError::Backend(error))),
+            };
+            let key = match K::from_bytes(&key) {
+                Ok(key) => key,
+                Err(DecodeError::DifferentLeaf) => continue,
+                Err(error) => return Some(Err(StoreError::Key(error))),
+            };
+            return Some(
+                K::Value::decode_value(&value)
+                    .map(|value| (key, value))
+                    .map_err(StoreError::Value),
+            );
+        }
+    }
+}
+
+/// An error encountered while using a [`Store`].
+#[derive(Debug)]
+pub enum StoreError<E> {
+    /// The backend of the store failed.
+    Backend(E),
+    /// A key in the store could not be decoded as a key in the schema.
+    Key(DecodeError),
+    /// A value in the store could not be decoded as the type declared for its key.
+    Value(ValueError),
+}
+
+impl<E> From<ValueError> for StoreError<E> {
+    fn from(error: ValueError) -> Self {
+        StoreError::Value(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for StoreError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Backend(error) => write!(f, "store backend error: {error}"),
+            StoreError::Key(error) => write!(f, "{error}"),
+            StoreError::Value(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for StoreError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Backend(error) => Some(error),
+            StoreError::Key(error) => Some(error),
+            StoreError::Value(error) => Some(error),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{Decode, Encode};
+
+/// A schema generated by [`schema!`](crate::schema).
+///
+/// This is implemented by the `Schema` struct at the root of every generated schema, and ties
+/// together the types which describe complete paths, keys and prefixes within that schema.
+pub trait Schema: Copy + 'static {
+    /// The root path of the schema.
+    type Path<'a>: Path<Schema = Self>;
+    /// The root path of the schema, as an owned path.
+    type OwnedPath: Path<Schema = Self>;
+    /// A key for any leaf of the schema.
+    type Key<'a>: Encode;
+    /// A key for any leaf of the schema, as an owned key.
+    type OwnedKey: Encode + Decode;
+    /// A prefix of any keys in the schema.
+    type Prefix<'a>: Prefix<Schema = Self>;
+    /// A prefix of any keys in the schema, as an owned prefix.
+    type OwnedPrefix: Prefix<Schema = Self>;
+
+    /// Get the root path of this schema.
+    fn root<'a>() -> Self::Path<'a>;
+
+    /// Get the root path of this schema, as an owned path.
+    fn owned_root() -> Self::OwnedPath;
+}
+
+/// A path to some node of a schema.
+///
+/// This is implemented by every `Path` and `OwnedPath` generated by [`schema!`](crate::schema).
+pub trait Path {
+    /// The schema this is a path within.
+    type Schema: Schema;
+    /// The parameters of the last segment of this path.
+    type Params: Params;
+}
+
+/// A prefix shared by all the keys beneath some node of a schema.
+///
+/// This is implemented by the `Prefix` and `OwnedPrefix` at the root of every schema generated by
+/// [`schema!`](crate::schema), which are the prefixes returned by a `Path`.
+pub trait Prefix: Encode {
+    /// The schema this is a prefix within.
+    type Schema: Schema;
+}
+
+/// The complete key for a leaf of a schema.
+///
+/// This is implemented by the `Key` and `OwnedKey` generated by [`schema!`](crate::schema) for
+/// each leaf of a schema, and associates each key with the type of value stored under it.
+pub trait Key: Encode {
+    /// The schema this is a key within.
+    type Schema: Schema;
+    /// The type of the value stored under this key.
+    type Value;
+}
+
+/// The complete key for a leaf of a schema, as an owned key which can be decoded.
+///
+/// This is implemented by the `OwnedKey` generated by [`schema!`](crate::schema) for each leaf of
+/// a schema.
+pub trait OwnedKey: Key + Decode {}
+
+/// The parameters of a single node of a schema.
+///
+/// This is implemented by every `Params` and `OwnedParams` generated by
+/// [`schema!`](crate::schema). A static node has no parameters, and a parameter node has exactly
+/// one.
+pub trait Params: Encode {
+    /// The name of the node: the name of its segment for a static node, or the name of its
+    /// parameter for a parameter node (or the empty string for the root of a schema).
+    const NAME: &'static str;
+}
+
+/// The parameters of a single node of a schema, as owned parameters which can be decoded.
+///
+/// This is implemented by every `OwnedParams` generated by [`schema!`](crate::schema).
+pub trait OwnedParams: Params + Decode {}
@@ -0,0 +1,103 @@
+use std::{error::Error, fmt};
+
+/// A type which can be stored as a value in a key-value store.
+///
+/// The value type of every leaf must implement this trait in order to be used with a
+/// [`Store`](crate::Store).
+pub trait Value: Sized {
+    /// Append the encoding of this value to the end of `buf`.
+    fn encode_value(&self, buf: &mut Vec<u8>);
+
+    /// Decode a value from exactly the bytes given.
+    fn decode_value(bytes: &[u8]) -> Result<Self, ValueError>;
+}
+
+/// An error encountered while decoding a value.
+#[derive(Debug)]
+pub struct ValueError(Box<dyn Error + Send + Sync>);
+
+impl ValueError {
+    /// Create a new value error from any underlying error.
+    pub fn new(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        ValueError(error.into())
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid value: {}", self.0)
+    }
+}
+
+impl Error for ValueError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+macro_rules! value_big_endian {
+    ($($ty:ty),*) => {
+        $(
+            impl Value for $ty {
+                fn encode_value(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn decode_value(bytes: &[u8]) -> Result<Self, ValueError> {
+                    bytes
+                        .try_into()
+                        .map(<$ty>::from_be_bytes)
+                        .map_err(ValueError::new)
+                }
+            }
+        )*
+    };
+}
+
+value_big_endian!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl Value for bool {
+    fn encode_value(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<Self, ValueError> {
+        match bytes {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(ValueError::new("invalid boolean")),
+        }
+    }
+}
+
+impl Value for String {
+    fn encode_value(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<Self, ValueError> {
+        String::from_utf8(bytes.to_vec()).map_err(ValueError::new)
+    }
+}
+
+impl Value for Vec<u8> {
+    fn encode_value(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self);
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<Self, ValueError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl Value for () {
+    fn encode_value(&self, _buf: &mut Vec<u8>) {}
+
+    fn decode_value(bytes: &[u8]) -> Result<Self, ValueError> {
+        if bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(ValueError::new("expected empty value"))
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "clap")]
+
+use schemata::{
+    clap::{Args, Command, FromArgMatches},
+    schema, Encode,
+};
+
+schema! {
+    #![schemata(clap)]
+
+    /// The current height.
+    height: u64;
+    account(id: u32) {
+        balance: u64;
+        nonce(epoch: u16, index: u8): u64;
+    }
+    point(x: f32, y: f32): String;
+    user(name: str): u64;
+    empty {}
+}
+
+fn parse(args: &[&str]) -> Result<OwnedKey, schemata::clap::Error> {
+    let matches = OwnedKey::augment_args(Command::new("key")).try_get_matches_from(args)?;
+    OwnedKey::from_arg_matches(&matches)
+}
+
+#[test]
+fn parse_keys() {
+    let key = parse(&["key", "height"]).unwrap();
+    assert_eq!(key.to_bytes(), Schema::root().height().key().to_bytes());
+
+    let key = parse(&["key", "account", "--id", "7", "balance"]).unwrap();
+    assert_eq!(
+        key.to_bytes(),
+        Schema::root().account(7).balance().key().to_bytes()
+    );
+
+    let key = parse(&[
+        "key", "account", "--id", "7", "nonce", "--epoch", "3", "--index", "1",
+    ])
+    .unwrap();
+    assert_eq!(
+        key.to_bytes(),
+        Schema::root().account(7).nonce(3, 1).key().to_bytes()
+    );
+
+    let key = parse(&["key", "point", "--x", "1.5", "--y", "-2"]).unwrap();
+    assert_eq!(
+        key.to_bytes(),
+        Schema::root().point(1.5, -2.0).key().to_bytes()
+    );
+
+    let key = parse(&["key", "user", "--name", "alice"]).unwrap();
+    assert_eq!(
+        key.to_bytes(),
+        Schema::root().user("alice").key().to_bytes()
+    );
+}
+
+#[test]
+fn reject_incomplete_keys() {
+    assert!(parse(&["key"]).is_err());
+    assert!(parse(&["key", "account", "--id", "7"]).is_err());
+    assert!(parse(&["key", "account", "balance"]).is_err());
+    assert!(parse(&["key", "point", "--x", "not a float", "--y", "0"]).is_err());
+}
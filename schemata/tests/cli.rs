@@ -0,0 +1,166 @@
+#![cfg(feature = "clap")]
+
+use schemata::{
+    clap::{Command as ClapCommand, FromArgMatches, Subcommand},
+    cli::{CliError, Command},
+    schema, MemoryStore, StoreError,
+};
+
+schema! {
+    #![schemata(cli)]
+
+    height: u64;
+    name: String;
+    account(id: u32) {
+        balance: i64;
+        nonce(epoch: u16, index: u8): Vec<u8>;
+    }
+}
+
+fn run(store: &mut MemoryStore, args: &[&str]) -> Result<String, String> {
+    let matches = Command::<Schema>::augment_subcommands(ClapCommand::new("store"))
+        .try_get_matches_from(args.iter().copied())
+        .map_err(|error| error.to_string())?;
+    let command = Command::<Schema>::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    command
+        .run(store, &mut out)
+        .map_err(|error: CliError<_>| error.to_string())?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn get_put_delete() {
+    let mut store = MemoryStore::new();
+
+    assert!(run(&mut store, &["store", "get", "height"]).is_err());
+    run(&mut store, &["store", "put", "height", "42"]).unwrap();
+    assert_eq!(
+        run(&mut store, &["store", "get", "height"]).unwrap(),
+        "42\n"
+    );
+
+    run(
+        &mut store,
+        &["store", "put", "account", "--id", "7", "balance", "-5"],
+    )
+    .unwrap();
+    assert_eq!(
+        run(
+            &mut store,
+            &["store", "get", "account", "--id", "7", "balance"]
+        )
+        .unwrap(),
+        "-5\n"
+    );
+
+    run(&mut store, &["store", "delete", "height"]).unwrap();
+    assert!(run(&mut store, &["store", "get", "height"]).is_err());
+}
+
+#[test]
+fn reject_invalid_commands() {
+    let mut store = MemoryStore::new();
+
+    // Missing or unparseable values
+    assert!(run(&mut store, &["store", "put", "height"]).is_err());
+    assert!(run(&mut store, &["store", "put", "height", "tall"]).is_err());
+    assert!(run(
+        &mut store,
+        &["store", "put", "account", "--id", "1", "nonce", "--epoch", "1", "--index", "2", "abc"]
+    )
+    .is_err());
+    assert!(run(
+        &mut store,
+        &["store", "put", "account", "--id", "1", "nonce", "--epoch", "1", "--index", "2", "aéb"]
+    )
+    .is_err());
+    assert!(run(
+        &mut store,
+        &["store", "put", "account", "--id", "1", "nonce", "--epoch", "1", "--index", "2", "+f"]
+    )
+    .is_err());
+
+    // Prefixes must give parameters in order, and before continuing past them
+    assert!(run(&mut store, &["store", "list", "account", "nonce"]).is_err());
+    assert!(run(
+        &mut store,
+        &["store", "list", "account", "--id", "1", "nonce", "--index", "2"]
+    )
+    .is_err());
+}
+
+#[test]
+fn list_prefixes() {
+    let mut store = MemoryStore::new();
+    for args in [
+        &["store", "put", "name", "node"][..],
+        &["store", "put", "account", "--id", "1", "balance", "10"],
+        &["store", "put", "account", "--id", "2", "balance", "20"],
+        &[
+            "store", "put", "account", "--id", "2", "nonce", "--epoch", "3", "--index", "4", "beef",
+        ],
+    ] {
+        run(&mut store, args).unwrap();
+    }
+
+    let all = run(&mut store, &["store", "list"]).unwrap();
+    assert_eq!(all.lines().count(), 4);
+    assert!(all.ends_with("name\tnode\n"));
+
+    assert_eq!(
+        run(&mut store, &["store", "list", "account", "--id", "2"]).unwrap(),
+        "account/2/balance\t20\naccount/2/nonce/3/4\tbeef\n"
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &["store", "list", "account", "--id", "2", "nonce", "--epoch", "3"]
+        )
+        .unwrap(),
+        "account/2/nonce/3/4\tbeef\n"
+    );
+    assert_eq!(
+        run(&mut store, &["store", "list", "account"])
+            .unwrap()
+            .lines()
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn list_stops_at_undecodable_key() {
+    use schemata::Backend;
+
+    let mut store = MemoryStore::new();
+    run(
+        &mut store,
+        &["store", "put", "account", "--id", "1", "balance", "10"],
+    )
+    .unwrap();
+    run(
+        &mut store,
+        &["store", "put", "account", "--id", "2", "balance", "20"],
+    )
+    .unwrap();
+    store
+        .put_raw(b"account/\0\0\0\x01/unknown".to_vec(), vec![])
+        .unwrap();
+
+    // The entry before the undecodable key is written, and then listing fails
+    let matches = Command::<Schema>::augment_subcommands(ClapCommand::new("store"))
+        .try_get_matches_from(["store", "list"])
+        .unwrap();
+    let command = Command::<Schema>::from_arg_matches(&matches).unwrap();
+    let mut out = Vec::new();
+    let error = command.run(&mut store, &mut out).unwrap_err();
+    assert!(matches!(error, CliError::Store(StoreError::Key(_))));
+    assert_eq!(String::from_utf8(out).unwrap(), "account/1/balance\t10\n");
+
+    // Entries under other prefixes can still be listed
+    assert_eq!(
+        run(&mut store, &["store", "list", "account", "--id", "2"]).unwrap(),
+        "account/2/balance\t20\n"
+    );
+}
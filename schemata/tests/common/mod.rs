@@ -0,0 +1,21 @@
+use std::fmt::Debug;
+
+use schemata::{Decode, Encode};
+
+/// Check that the values given, which are in ascending order, have encodings in ascending order
+/// and decode back to themselves.
+pub fn assert_order_preserving<T: Encode + Decode + Debug>(values: &[T]) {
+    let encoded: Vec<Vec<u8>> = values.iter().map(Encode::to_bytes).collect();
+    for (value, bytes) in values.iter().zip(&encoded) {
+        let decoded = T::from_bytes(bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), *bytes, "{value:?} did not round-trip");
+    }
+    for (pair, bytes) in values.windows(2).zip(encoded.windows(2)) {
+        assert!(
+            bytes[0] < bytes[1],
+            "{:?} should sort before {:?}",
+            pair[0],
+            pair[1]
+        );
+    }
+}
@@ -0,0 +1,9 @@
+// The compiler names some standard types differently when `clap` is among the dependencies, which
+// would change the expected errors
+#![cfg(not(feature = "clap"))]
+
+/// Check that schemas with bad parameter types are rejected with errors pointing at the types.
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,144 @@
+use std::fmt::Debug;
+
+use schemata::{schema, Decode, Encode, KeyPart};
+
+mod common;
+
+use common::assert_order_preserving;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, KeyPart)]
+pub struct AssetId([u8; 4]);
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, KeyPart)]
+pub struct Pair {
+    start: AssetId,
+    end: AssetId,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, KeyPart)]
+pub enum PoolKind {
+    Stable,
+    Weighted(u8, u8),
+    Concentrated { fee: u32, name: String },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, KeyPart)]
+pub struct Named {
+    name: String,
+    n: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, KeyPart)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, KeyPart)]
+pub struct Wrapper<T>(T);
+
+/// Check that the values given are in ascending order by their derived `Ord`, that their encodings
+/// are too, and that they decode back to equal values.
+fn assert_derived_order_preserved<T: Encode + Decode + Debug + Ord>(values: &[T]) {
+    assert!(
+        values.is_sorted(),
+        "{values:?} should be in ascending order"
+    );
+    assert_order_preserving(values);
+    for value in values {
+        assert_eq!(&T::from_bytes(&value.to_bytes()).unwrap(), value);
+    }
+}
+
+#[test]
+fn structs() {
+    assert_derived_order_preserved(&[
+        AssetId([0, 0, 0, 1]),
+        AssetId([0, 1, 0, 0]),
+        AssetId([1; 4]),
+    ]);
+    assert_derived_order_preserved(&[
+        Pair {
+            start: AssetId([0; 4]),
+            end: AssetId([9; 4]),
+        },
+        Pair {
+            start: AssetId([1; 4]),
+            end: AssetId([0; 4]),
+        },
+    ]);
+    assert_derived_order_preserved(&[Wrapper(-1i64), Wrapper(0), Wrapper(3)]);
+
+    // A string followed by a field starting with `0xFF` can still be told apart from it
+    let named = |name: &str, n| Named {
+        name: name.to_string(),
+        n,
+    };
+    assert_derived_order_preserved(&[
+        named("", 0),
+        named("", 255),
+        named("\0", 0),
+        named("\0", 255),
+        named("a", 255),
+    ]);
+    assert_eq!(AssetId([1, 2, 3, 4]).to_bytes(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn enums() {
+    assert_derived_order_preserved(&[
+        PoolKind::Stable,
+        PoolKind::Weighted(1, 9),
+        PoolKind::Weighted(2, 0),
+        PoolKind::Concentrated {
+            fee: 30,
+            name: "b".to_string(),
+        },
+        PoolKind::Concentrated {
+            fee: 30,
+            name: "bc".to_string(),
+        },
+    ]);
+    assert_derived_order_preserved(&[Side::Buy, Side::Sell]);
+    assert_eq!(PoolKind::Weighted(1, 2).to_bytes(), [1, 1, 2]);
+    assert_eq!(
+        PoolKind::from_bytes(&[3]),
+        Err(schemata::DecodeError::InvalidParam { ty: "PoolKind" })
+    );
+}
+
+#[test]
+fn fixed_lengths() {
+    assert_eq!(AssetId::FIXED_LEN, Some(4));
+    assert_eq!(Pair::FIXED_LEN, Some(8));
+    assert_eq!(Side::FIXED_LEN, Some(1));
+    assert_eq!(PoolKind::FIXED_LEN, None);
+    assert_eq!(Wrapper::<u16>::FIXED_LEN, Some(2));
+}
+
+schema! {
+    pool(kind: PoolKind, pair: Pair) {
+        reserves(#[by_value] side: Side): u64;
+    }
+}
+
+#[test]
+fn derived_parameters() {
+    let (kind, pair, side) = (
+        PoolKind::Weighted(1, 2),
+        Pair {
+            start: AssetId([1; 4]),
+            end: AssetId([2; 4]),
+        },
+        Side::Sell,
+    );
+    let key = Schema::root().pool(&kind, &pair).reserves(side).key();
+    assert_eq!(
+        key.to_bytes(),
+        b"pool/\x01\x01\x02/\x01\x01\x01\x01\x02\x02\x02\x02/reserves/\x01"
+    );
+    assert_eq!(
+        OwnedKey::from_bytes(&key.to_bytes()).unwrap().to_bytes(),
+        key.to_bytes()
+    );
+}
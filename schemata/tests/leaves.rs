@@ -0,0 +1,95 @@
+use schemata::{schema, Decode, Encode};
+
+schema! {
+    height: u64;
+    baz(x: f32, y: f32) {
+        foo: u64;
+        bar(id: u8): String;
+    }
+    account(id: u32) {
+        sub(id: u8) {
+            balance: u64;
+        }
+    }
+    empty {}
+}
+
+fn decode(bytes: &[u8]) -> OwnedKey {
+    OwnedKey::from_bytes(bytes).unwrap()
+}
+
+#[test]
+fn match_on_leaf() {
+    let key = Schema::root().baz(1.5, -2.0).foo().key().to_bytes();
+    match Leaf::from(decode(&key)) {
+        Leaf::baz {
+            x,
+            y,
+            child: baz::x::y::Leaf::foo,
+        } => assert_eq!((x, y), (1.5, -2.0)),
+        _ => panic!("expected baz/{{x}}/{{y}}/foo"),
+    }
+
+    let key = Schema::root().account(7).sub(3).balance().key().to_bytes();
+    let Leaf::account {
+        id: 7,
+        child:
+            account::id::Leaf::sub {
+                id: 3,
+                child: account::id::sub::id::Leaf::balance,
+            },
+    } = Leaf::from(decode(&key))
+    else {
+        panic!("expected account/7/sub/3/balance");
+    };
+
+    assert!(matches!(
+        Leaf::from(decode(&Schema::root().height().key().to_bytes())),
+        Leaf::height
+    ));
+}
+
+struct Describe;
+
+impl Visitor for Describe {
+    type Output = String;
+
+    fn height(&mut self) -> String {
+        "height".to_string()
+    }
+
+    fn baz_foo(&mut self, x: f32, y: f32) -> String {
+        format!("foo at ({x}, {y})")
+    }
+
+    fn baz_bar(&mut self, x: f32, y: f32, id: u8) -> String {
+        format!("bar {id} at ({x}, {y})")
+    }
+
+    fn account_sub_balance(&mut self, id: u32, sub_id: u8) -> String {
+        format!("balance of {id}/{sub_id}")
+    }
+}
+
+#[test]
+fn visit_keys() {
+    let keys = [
+        Schema::root().height().key().to_bytes(),
+        Schema::root().baz(1.5, -2.0).foo().key().to_bytes(),
+        Schema::root().baz(0.0, 1.0).bar(9).key().to_bytes(),
+        Schema::root().account(7).sub(3).balance().key().to_bytes(),
+    ];
+    let described: Vec<_> = keys
+        .iter()
+        .map(|key| decode(key).visit(&mut Describe))
+        .collect();
+    assert_eq!(
+        described,
+        [
+            "height",
+            "foo at (1.5, -2)",
+            "bar 9 at (0, 1)",
+            "balance of 7/3"
+        ]
+    );
+}
@@ -0,0 +1,27 @@
+use schemata::{schema, Encode};
+
+schema! {
+    #![schemata(names(Schema = "State", Key = "StateKey", OwnedKey = "OwnedStateKey"))]
+
+    height: u64;
+    account(id: u32) {
+        balance: u64;
+    }
+}
+
+#[test]
+fn renamed_types() {
+    fn key_bytes<K: schemata::Key<Schema = State>>(key: K) -> Vec<u8> {
+        key.to_bytes()
+    }
+
+    let key: height::StateKey = State::root().height().key();
+    assert_eq!(key_bytes(key), b"height");
+    assert_eq!(
+        key_bytes(State::root().account(1).balance().key()),
+        b"account/\0\0\0\x01/balance"
+    );
+
+    let decoded: OwnedStateKey = schemata::Decode::from_bytes(b"height").unwrap();
+    assert_eq!(decoded.to_bytes(), b"height");
+}
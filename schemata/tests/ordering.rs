@@ -0,0 +1,194 @@
+use schemata::{schema, Decode, Encode, MemoryStore, Store};
+
+mod common;
+
+use common::assert_order_preserving;
+
+#[test]
+fn unsigned() {
+    assert_order_preserving(&[0u8, 1, 127, 128, 255]);
+    assert_order_preserving(&[0u64, 1, 255, 256, u64::MAX]);
+    assert_order_preserving(&[0usize, 1, 1 << 20]);
+}
+
+#[test]
+fn signed() {
+    assert_order_preserving(&[i8::MIN, -1, 0, 1, i8::MAX]);
+    assert_order_preserving(&[i64::MIN, -256, -1, 0, 1, 256, i64::MAX]);
+    assert_order_preserving(&[i128::MIN, -1, 0, i128::MAX]);
+    assert_order_preserving(&[isize::MIN, 0, isize::MAX]);
+}
+
+#[test]
+fn float() {
+    assert_order_preserving(&[
+        -f32::NAN,
+        f32::NEG_INFINITY,
+        f32::MIN,
+        -1.5,
+        -f32::MIN_POSITIVE,
+        -0.0,
+        0.0,
+        f32::MIN_POSITIVE,
+        1.5,
+        f32::MAX,
+        f32::INFINITY,
+        f32::NAN,
+    ]);
+    assert_order_preserving(&[f64::NEG_INFINITY, -1e300, -0.0, 0.0, 1e-300, f64::INFINITY]);
+}
+
+#[test]
+fn strings_and_bytes() {
+    assert_order_preserving(&[false, true]);
+    assert_order_preserving(&[
+        String::new(),
+        "\0".to_string(),
+        "\0\0".to_string(),
+        "\0a".to_string(),
+        "a".to_string(),
+        "a\0".to_string(),
+        "ab".to_string(),
+        "b".to_string(),
+    ]);
+    assert_order_preserving(&[vec![], vec![0u8], vec![0, 0xff], vec![1], vec![0xff, 0]]);
+}
+
+#[test]
+fn composites() {
+    assert_order_preserving(&[[0u8, 0], [0, 1], [1, 0]]);
+    assert_order_preserving(&[
+        ("a".to_string(), 2u8),
+        ("a".to_string(), 3),
+        ("a\0".to_string(), 0),
+        ("b".to_string(), 1),
+    ]);
+    assert_order_preserving(&[None, Some(-1i32), Some(0), Some(7)]);
+}
+
+#[test]
+fn byte_strings_followed_by_0xff() {
+    // The end of a byte string must not be mistaken for an escaped zero in it, whatever follows
+    assert_order_preserving(&[
+        (vec![], 0u8),
+        (vec![], 255),
+        (vec![0], 0),
+        (vec![0], 255),
+        (vec![0, 0], 255),
+    ]);
+    assert_order_preserving(&[
+        (String::new(), 255u8),
+        ("\0".to_string(), 255),
+        ("a".to_string(), 255),
+    ]);
+    assert_eq!(
+        <(Vec<u8>, u8)>::from_bytes(&(vec![], 255u8).to_bytes()),
+        Ok((vec![], 255))
+    );
+
+    // No encoding is the start of another, longer one
+    let short = (Vec::<u8>::new(), 255u8).to_bytes();
+    let long = (vec![0u8], 0u8).to_bytes();
+    assert!(!long.starts_with(&short));
+
+    let t = (vec![], 255);
+    let key = Schema::root().item(&t).key().to_bytes();
+    let decoded = OwnedKey::from_bytes(&key).unwrap();
+    assert_eq!(decoded.to_bytes(), key);
+}
+
+#[test]
+fn fixed_lengths() {
+    use schemata::KeyPart;
+
+    assert_eq!(u32::FIXED_LEN, Some(4));
+    assert_eq!(<[u16; 3]>::FIXED_LEN, Some(6));
+    assert_eq!(<(u8, i64, bool)>::FIXED_LEN, Some(10));
+    assert_eq!(<(u8, String)>::FIXED_LEN, None);
+    assert_eq!(<str>::FIXED_LEN, None);
+    assert_eq!(<Option<u8>>::FIXED_LEN, None);
+}
+
+schema! {
+    point(x: f32, y: i16): u8;
+    named(name: String, tag: Option<[u8; 2]>): u8;
+    labelled(label: str, data: [u8]): u8;
+    item(t: (Vec<u8>, u8)): u8;
+}
+
+#[test]
+fn store_iterates_in_parameter_order() {
+    let mut store = MemoryStore::new();
+    let points = [(-2.5f32, 3i16), (-2.5, -3), (0.0, 0), (1.0, -1), (-10.0, 7)];
+    for (i, &(x, y)) in points.iter().enumerate() {
+        store
+            .put(&Schema::root().point(x, y).key(), &(i as u8))
+            .unwrap();
+    }
+
+    let mut sorted = points;
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    let expected: Vec<Vec<u8>> = sorted
+        .iter()
+        .map(|&(x, y)| Schema::root().point(x, y).key().to_bytes())
+        .collect();
+    let actual: Vec<Vec<u8>> = store.iter().map(|(key, _)| key.to_vec()).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn keys_sort_by_encoding() {
+    use std::collections::{BTreeSet, HashSet};
+
+    let points = [(-2.5f32, 3i16), (-2.5, -3), (0.0, 0), (1.0, -1), (-10.0, 7)];
+    let keys: Vec<OwnedKey> = points
+        .iter()
+        .map(|&(x, y)| OwnedKey::from_bytes(&Schema::root().point(x, y).key().to_bytes()).unwrap())
+        .collect();
+
+    let sorted: Vec<Vec<u8>> = keys
+        .iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(Encode::to_bytes)
+        .collect();
+    let mut expected: Vec<Vec<u8>> = keys.iter().map(Encode::to_bytes).collect();
+    expected.sort();
+    assert_eq!(sorted, expected);
+
+    let unique: HashSet<_> = keys.iter().chain(&keys).collect();
+    assert_eq!(unique.len(), keys.len());
+}
+
+#[test]
+fn string_parameters() {
+    let (name, tag) = ("a/b".to_string(), Some(*b"xy"));
+    let key = Schema::root().named(&name, &tag).key();
+    assert_eq!(key.to_bytes(), b"named/a/b\0\x01/\x01xy");
+    assert_eq!(key.to_string(), "named/\"a/b\"/Some([120, 121])");
+
+    let decoded = OwnedKey::from_bytes(&key.to_bytes()).unwrap();
+    assert_eq!(decoded.to_bytes(), key.to_bytes());
+}
+
+#[test]
+fn unsized_parameters() {
+    // Borrowed keys can be built straight from a `&str` and a `&[u8]`
+    let key = Schema::root().labelled("abc", &[1, 0, 2]).key();
+    assert_eq!(key.to_bytes(), b"labelled/abc\0\x01/\x01\0\xff\x02\0\x01");
+    assert_eq!(key.to_string(), "labelled/\"abc\"/[1, 0, 2]");
+
+    // Owned keys hold a `String` and a `Vec<u8>` instead
+    let owned = labelled::label::data::OwnedKey::from_bytes(&key.to_bytes()).unwrap();
+    assert!(owned == key.to_owned());
+    assert!(owned.as_borrowed() == key);
+
+    let path = Schema::owned_labelled("abc".to_string(), vec![1, 0, 2]);
+    assert!(path.into_key() == owned);
+    match Leaf::from(owned.into_root()) {
+        Leaf::labelled { label, data } => {
+            assert_eq!((label, data), ("abc".to_string(), vec![1, 0, 2]))
+        }
+        _ => panic!("expected labelled/{{label}}/{{data}}"),
+    }
+}
@@ -0,0 +1,123 @@
+use std::marker::PhantomData;
+
+use schemata::{schema, Encode};
+
+pub struct Balance(pub u64);
+
+schema! {
+    //! A schema exercising the different shapes of node.
+
+    /// A namespace with nothing in it.
+    empty {}
+    /// A leaf with a parameter.
+    leaf(
+        /// The parameter of the leaf.
+        x: u64,
+    ): String;
+    nested {
+        inner(a: u8) {
+            value: u32;
+            balance: Balance;
+        }
+    }
+    #[rename = "legacy-store"]
+    legacy {
+        #[rename = "0"]
+        zero(id: u8): u64;
+    }
+}
+
+#[test]
+fn parameterized_leaf() {
+    assert_eq!(
+        Schema::root().leaf(7).key().to_bytes(),
+        b"leaf/\0\0\0\0\0\0\0\x07"
+    );
+}
+
+#[test]
+fn nested_prefixes() {
+    let key = Schema::root().nested().inner(1).value().key().to_bytes();
+    assert_eq!(key, b"nested/inner/\x01/value");
+    assert_eq!(Schema::root().nested().prefix().to_bytes(), b"nested/");
+    assert_eq!(
+        Schema::root().nested().inner(1).prefix().to_bytes(),
+        b"nested/inner/\x01/"
+    );
+    assert_eq!(Schema::root().empty().prefix().to_bytes(), b"empty/");
+}
+
+#[test]
+fn renamed_segments() {
+    use schemata::Decode;
+
+    let key = Schema::root().legacy().zero(3).key().to_bytes();
+    assert_eq!(key, b"legacy-store/0/\x03");
+    assert_eq!(
+        Schema::root().legacy().prefix().to_bytes(),
+        b"legacy-store/"
+    );
+
+    let decoded = <Schema as schemata::Schema>::OwnedKey::from_bytes(&key).unwrap();
+    assert_eq!(decoded.to_bytes(), key);
+    assert!(<Schema as schemata::Schema>::OwnedKey::from_bytes(b"legacy/zero/\x03").is_err());
+}
+
+#[test]
+fn decode_round_trip() {
+    use schemata::{Decode, DecodeError};
+
+    for key in [
+        Schema::root().leaf(7).key().to_bytes(),
+        Schema::root().nested().inner(1).value().key().to_bytes(),
+    ] {
+        assert_eq!(OwnedKey::from_bytes(&key).unwrap().to_bytes(), key);
+    }
+
+    assert!(matches!(
+        OwnedKey::from_bytes(b"empty/"),
+        Err(DecodeError::NoMatchingSegment { expected: [] })
+    ));
+    assert!(matches!(
+        OwnedKey::from_bytes(b"leaf/\0\0"),
+        Err(DecodeError::UnexpectedEnd)
+    ));
+}
+
+fn value_type<K: schemata::Key>(_: &K) -> PhantomData<K::Value> {
+    PhantomData
+}
+
+#[test]
+fn key_value_types() {
+    let _: PhantomData<String> = value_type(&Schema::root().leaf(7).key());
+    let _: PhantomData<u32> = value_type(&Schema::root().nested().inner(1).value().key());
+    let _: PhantomData<Balance> = value_type(&Schema::root().nested().inner(1).balance().key());
+}
+
+mod keywords {
+    schemata::schema! {
+        r#type: u64;
+        r#match(r#ref: u8) {
+            r#in: u64;
+        }
+    }
+}
+
+#[test]
+fn raw_identifiers() {
+    use schemata::{Decode, Params};
+
+    // Raw identifiers are written in keys without their `r#` prefix
+    let key = keywords::Schema::root().r#type().key();
+    assert_eq!(key.to_bytes(), b"type");
+    assert_eq!(key.to_string(), "type");
+
+    let key = keywords::Schema::root().r#match(3).r#in().key();
+    assert_eq!(key.to_bytes(), b"match/\x03/in");
+    assert_eq!(key.to_string(), "match/3/in");
+    assert_eq!(keywords::r#match::r#ref::Params::NAME, "ref");
+
+    let decoded = keywords::OwnedKey::from_bytes(&key.to_bytes()).unwrap();
+    assert_eq!(decoded.to_bytes(), key.to_bytes());
+}
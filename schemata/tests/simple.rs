@@ -7,3 +7,194 @@ schema! {
         foo: u64;
     }
 }
+
+#[test]
+fn navigate() {
+    let (x, y) = (1.5, 2.0);
+    assert!(Schema::root().baz(x, y).foo() == Schema::root().baz(x, y).foo());
+    assert!(Schema::root().baz(x, y).foo() != Schema::root().baz(y, x).foo());
+    assert!(foo() == Schema::root().foo());
+    assert!(bar() == Schema::root().bar());
+}
+
+#[test]
+fn path_to_key_and_prefix() {
+    let (x, y) = (1.5, 2.0);
+    assert!(foo().key() == Schema::root().foo().key());
+    assert!(Schema::root().baz(x, y).foo().key() != Schema::root().baz(y, x).foo().key());
+    assert!(Schema::root().prefix() == Schema::root().prefix());
+    assert!(Schema::owned_root().into_prefix() == Schema::owned_root().into_prefix());
+}
+
+#[test]
+fn encode() {
+    use schemata::Encode;
+
+    let (x, y) = (1.5f32, 2.0f32);
+    assert_eq!(foo().key().to_bytes(), b"foo");
+    assert_eq!(bar().key().to_bytes(), b"bar");
+
+    let key = Schema::root().baz(x, y).foo().key().to_bytes();
+    let mut expected = b"baz/".to_vec();
+    x.encode(&mut expected);
+    expected.push(b'/');
+    y.encode(&mut expected);
+    expected.extend_from_slice(b"/foo");
+    assert_eq!(key, expected);
+
+    // Every prefix is a strict prefix of the keys beneath it
+    for prefix in [Schema::root().prefix(), Schema::root().baz(x, y).prefix()] {
+        let prefix = prefix.to_bytes();
+        assert!(key.starts_with(&prefix) && key.len() > prefix.len());
+    }
+}
+
+#[test]
+fn decode() {
+    use schemata::{Decode, DecodeError, Encode};
+
+    let (x, y) = (1.5f32, 2.0f32);
+    for key in [
+        foo().key().to_bytes(),
+        bar().key().to_bytes(),
+        Schema::root().baz(x, y).foo().key().to_bytes(),
+    ] {
+        assert_eq!(OwnedKey::from_bytes(&key).unwrap().to_bytes(), key);
+    }
+
+    assert!(matches!(
+        OwnedKey::from_bytes(b"qux"),
+        Err(DecodeError::NoMatchingSegment {
+            expected: ["foo", "bar", "baz"]
+        })
+    ));
+    assert!(matches!(
+        OwnedKey::from_bytes(b"foo/bar"),
+        Err(DecodeError::TrailingBytes { len: 4 })
+    ));
+    assert!(matches!(
+        OwnedKey::from_bytes(b"foobar"),
+        Err(DecodeError::NoMatchingSegment { .. })
+    ));
+    assert!(matches!(
+        OwnedKey::from_bytes(b"baz/"),
+        Err(DecodeError::UnexpectedEnd)
+    ));
+}
+
+#[test]
+fn generic_traits() {
+    use schemata::{Decode, DecodeError, Encode};
+
+    fn key_bytes<K: schemata::Key<Schema = Schema>>(key: K) -> Vec<u8> {
+        key.to_bytes()
+    }
+
+    fn decode_any<S: schemata::Schema>(bytes: &[u8]) -> Result<S::OwnedKey, DecodeError> {
+        S::OwnedKey::from_bytes(bytes)
+    }
+
+    let (x, y) = (1.5f32, 2.0f32);
+    let key = key_bytes(Schema::root().baz(x, y).foo().key());
+    assert_eq!(decode_any::<Schema>(&key).unwrap().to_bytes(), key);
+
+    assert_eq!(<foo::Params as schemata::Params>::NAME, "foo");
+    assert_eq!(
+        foo::OwnedKey::from_bytes(b"foo").unwrap().to_bytes(),
+        b"foo"
+    );
+    assert!(matches!(
+        foo::OwnedKey::from_bytes(b"bar"),
+        Err(DecodeError::DifferentLeaf)
+    ));
+}
+
+#[test]
+fn equality_by_encoding() {
+    fn assert_eq_impl<T: Eq>(a: T, b: T) -> bool {
+        a == b
+    }
+
+    let (nan, zero, neg_zero) = (f32::NAN, 0.0f32, -0.0f32);
+    let key = |x, y| Schema::root().baz(x, y).foo().key();
+
+    // Parameters compare by encoding, so keys with `f32` parameters are `Eq`, and `NaN` is
+    // equal to itself while `0.0` and `-0.0` are distinct locations
+    assert!(assert_eq_impl(key(nan, zero), key(nan, zero)));
+    assert!(!assert_eq_impl(key(zero, zero), key(neg_zero, zero)));
+    assert!(assert_eq_impl(
+        Schema::root().baz(nan, nan),
+        Schema::root().baz(nan, nan)
+    ));
+}
+
+#[test]
+fn borrowed_and_owned() {
+    let (x, y) = (1.5f32, 2.0f32);
+    let key = Schema::root().baz(x, y).foo().key();
+
+    // Owned keys outlive the parameters they were built from
+    let owned: baz::x::y::foo::OwnedKey = {
+        let (x, y) = (x, y);
+        Schema::root().baz(x, y).foo().key().to_owned()
+    };
+    assert!(owned.as_borrowed() == key);
+    assert!(baz::x::y::foo::OwnedKey::from(key) == owned);
+    assert!(baz::x::y::foo::Key::from(&owned) == key);
+
+    let path = Schema::root().baz(x, y);
+    assert!(path.to_owned().as_borrowed() == path);
+    assert!(path.prefix().to_owned().as_borrowed() == path.prefix());
+    assert!(Schema::root().prefix().to_owned() == Schema::owned_root().into_prefix());
+}
+
+#[test]
+fn display() {
+    use schemata::Decode;
+
+    let (x, y) = (1.5f32, 2.0f32);
+    let key = Schema::root().baz(x, y).foo().key();
+    assert_eq!(key.to_string(), "baz/1.5/2.0/foo");
+    assert_eq!(format!("{key:?}"), "baz/1.5/2.0/foo");
+
+    let owned = OwnedKey::from_bytes(&schemata::Encode::to_bytes(&key)).unwrap();
+    assert_eq!(owned.to_string(), "baz/1.5/2.0/foo");
+    assert_eq!(
+        Schema::root().baz(x, y).prefix().to_string(),
+        "baz/1.5/2.0/"
+    );
+    assert_eq!(Schema::root().prefix().to_string(), "");
+}
+
+#[test]
+fn parameterized_root_functions() {
+    let (x, y) = (1.5f32, 2.0f32);
+    assert!(baz(x, y) == Schema::root().baz(x, y));
+    assert!(baz(x, y).foo().key() == Schema::root().baz(x, y).foo().key());
+
+    // Owned paths are navigated the same way, taking their parameters by value
+    let owned = Schema::owned_baz(x, y);
+    assert!(owned == Schema::owned_root().baz(x, y));
+    assert!(owned.as_borrowed() == baz(x, y));
+    assert!(owned.foo().into_key() == baz(x, y).foo().key().to_owned());
+    assert!(Schema::owned_foo().into_key() == foo().key().to_owned());
+}
+
+mod arrays {
+    schemata::schema! {
+        hash(hash: [u8; 32]): u64;
+        wide(words: [u64; 8]): u64;
+        copied(#[by_value] words: [u64; 8]): u64;
+    }
+}
+
+#[test]
+fn small_arrays_by_value() {
+    // Arrays up to 32 bytes are passed by value, and bigger ones by reference unless asked
+    let hash = arrays::Schema::root().hash([7; 32]).key();
+    let wide = arrays::Schema::root().wide(&[7; 8]).key();
+    let copied = arrays::Schema::root().copied([7; 8]).key();
+    assert_eq!(hash.to_string(), format!("hash/{:?}", [7u8; 32]));
+    assert_eq!(wide.to_string(), format!("wide/{:?}", [7u64; 8]));
+    assert_eq!(copied.to_string(), format!("copied/{:?}", [7u64; 8]));
+}
@@ -0,0 +1,232 @@
+use schemata::{schema, Backend, Encode, MemoryStore, Order, Store};
+
+schema! {
+    height: u64;
+    name: String;
+    account(id: u32) {
+        balance: u64;
+    }
+}
+
+#[test]
+fn get_put_delete() {
+    let mut store = MemoryStore::new();
+
+    store.put(&height().key(), &100).unwrap();
+    store.put(&name().key(), &"penumbra".to_string()).unwrap();
+    store
+        .put(&Schema::root().account(1).balance().key(), &5)
+        .unwrap();
+
+    assert_eq!(store.get(&height().key()).unwrap(), Some(100));
+    assert_eq!(
+        store.get(&name().key()).unwrap().as_deref(),
+        Some("penumbra")
+    );
+    assert_eq!(
+        store
+            .get(&Schema::root().account(1).balance().key())
+            .unwrap(),
+        Some(5)
+    );
+    assert_eq!(
+        store
+            .get(&Schema::root().account(2).balance().key())
+            .unwrap(),
+        None
+    );
+
+    store.delete(&height().key()).unwrap();
+    assert_eq!(store.get(&height().key()).unwrap(), None);
+}
+
+#[test]
+fn wrong_value_encoding() {
+    let mut store = MemoryStore::new();
+    store
+        .put_raw(b"height".to_vec(), b"short".to_vec())
+        .unwrap();
+    assert!(matches!(
+        store.get(&height().key()),
+        Err(schemata::StoreError::Value(_))
+    ));
+}
+
+#[test]
+fn snapshot_and_prefix_scan() {
+    let mut store = MemoryStore::new();
+    store.put(&height().key(), &100).unwrap();
+    for id in [3, 1, 2] {
+        store
+            .put(
+                &Schema::root().account(id).balance().key(),
+                &(id as u64 * 10),
+            )
+            .unwrap();
+    }
+
+    let snapshot = store.clone();
+    assert_eq!(snapshot, store);
+    store.delete(&height().key()).unwrap();
+    assert_ne!(snapshot, store);
+    assert_eq!(store.len(), 3);
+
+    // Entries beneath a prefix come back in order of their keys
+    let prefix = Schema::root().account(2).prefix().to_bytes();
+    let entries: Vec<_> = store
+        .scan_raw(&prefix, None, Order::Ascending)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        entries,
+        vec![(
+            Schema::root().account(2).balance().key().to_bytes(),
+            20u64.to_be_bytes().to_vec()
+        )]
+    );
+    let all: Vec<_> = store
+        .scan_raw(&[], None, Order::Ascending)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(all.len(), 3);
+    assert!(all.windows(2).all(|pair| pair[0].0 < pair[1].0));
+}
+
+#[test]
+fn raw_scan_order_and_cursor() {
+    let mut store = MemoryStore::new();
+    for id in 0..5 {
+        store
+            .put(&Schema::root().account(id).balance().key(), &(id as u64))
+            .unwrap();
+    }
+    store.put(&height().key(), &100).unwrap();
+
+    let prefix = b"account/";
+    let scan = |start_after: Option<u32>, order| -> Vec<u64> {
+        let start_after =
+            start_after.map(|id| Schema::root().account(id).balance().key().to_bytes());
+        store
+            .scan_raw(prefix, start_after.as_deref(), order)
+            .map(|entry| u64::from_be_bytes(entry.unwrap().1.try_into().unwrap()))
+            .collect()
+    };
+
+    assert_eq!(scan(None, Order::Ascending), [0, 1, 2, 3, 4]);
+    assert_eq!(scan(None, Order::Descending), [4, 3, 2, 1, 0]);
+    assert_eq!(scan(Some(1), Order::Ascending), [2, 3, 4]);
+    assert_eq!(scan(Some(3), Order::Descending), [2, 1, 0]);
+    assert_eq!(scan(Some(4), Order::Ascending), [] as [u64; 0]);
+    assert_eq!(scan(Some(0), Order::Descending), [] as [u64; 0]);
+}
+
+#[test]
+fn typed_scan() {
+    let mut store = MemoryStore::new();
+    store.put(&height().key(), &100).unwrap();
+    store.put(&name().key(), &"penumbra".to_string()).unwrap();
+    store
+        .put(&Schema::root().account(1).balance().key(), &5)
+        .unwrap();
+
+    // Only entries for the requested leaf are returned, decoded as that leaf's types
+    let heights: Vec<(height::OwnedKey, u64)> = store
+        .scan(&Schema::root().prefix())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(heights.len(), 1);
+    assert_eq!(heights[0].0.to_bytes(), b"height");
+    assert_eq!(heights[0].1, 100);
+
+    let names: Vec<(name::OwnedKey, String)> = store
+        .scan_from(&Schema::owned_root().into_prefix(), None, Order::Descending)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(names.len(), 1);
+    assert_eq!(names[0].1, "penumbra");
+
+    // Starting after the only matching entry leaves nothing
+    let after: Vec<(name::OwnedKey, String)> = store
+        .scan_from(
+            &Schema::root().prefix(),
+            Some(&names[0].0),
+            Order::Ascending,
+        )
+        .map(Result::unwrap)
+        .collect();
+    assert!(after.is_empty());
+}
+
+mod ledger {
+    schemata::schema! {
+        account(id: u32) {
+            balance: u64;
+            deposit(seq: u32): u64;
+        }
+    }
+}
+
+#[test]
+fn typed_scan_beneath_parameterized_prefix() {
+    use ledger::account::id::deposit::seq::OwnedKey as DepositKey;
+
+    let mut store = MemoryStore::new();
+    for id in [1, 2, 3] {
+        let account = ledger::Schema::root().account(id);
+        store.put(&account.balance().key(), &(id as u64)).unwrap();
+        for seq in 0..4 {
+            store
+                .put(&account.deposit(seq).key(), &(id as u64 * 10 + seq as u64))
+                .unwrap();
+        }
+    }
+
+    // Only the deposits of the account at the prefix are visited, skipping its balance
+    let prefix = ledger::Schema::root().account(2).prefix();
+    let scan = |start_after: Option<u32>, order| -> Vec<u64> {
+        let start_after = start_after.map(|seq| {
+            ledger::Schema::owned_root()
+                .account(2)
+                .deposit(seq)
+                .into_key()
+        });
+        store
+            .scan_from::<DepositKey>(&prefix, start_after.as_ref(), order)
+            .map(|entry| entry.unwrap().1)
+            .collect()
+    };
+    assert_eq!(scan(None, Order::Ascending), [20, 21, 22, 23]);
+    assert_eq!(scan(None, Order::Descending), [23, 22, 21, 20]);
+
+    // Resuming past a cursor in the middle of the range continues in either direction
+    assert_eq!(scan(Some(1), Order::Ascending), [22, 23]);
+    assert_eq!(scan(Some(2), Order::Descending), [21, 20]);
+    assert_eq!(scan(Some(3), Order::Ascending), [] as [u64; 0]);
+
+    // The keys visited are the ones the values were stored under
+    let keys: Vec<Vec<u8>> = store
+        .scan::<DepositKey>(&prefix)
+        .map(|entry| entry.unwrap().0.to_bytes())
+        .collect();
+    let expected: Vec<Vec<u8>> = (0..4)
+        .map(|seq| {
+            ledger::Schema::root()
+                .account(2)
+                .deposit(seq)
+                .key()
+                .to_bytes()
+        })
+        .collect();
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn scan_reports_undecodable_keys() {
+    let mut store = MemoryStore::new();
+    store.put_raw(b"garbage".to_vec(), vec![]).unwrap();
+    let mut scan = store.scan::<height::OwnedKey>(&Schema::root().prefix());
+    assert!(matches!(
+        scan.next(),
+        Some(Err(schemata::StoreError::Key(_)))
+    ));
+}
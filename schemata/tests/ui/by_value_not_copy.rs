@@ -0,0 +1,10 @@
+use schemata::{schema, KeyPart};
+
+#[derive(Clone, Debug, KeyPart)]
+pub struct Label(u8);
+
+schema! {
+    label(#[by_value] label: Label): u64;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use schemata::schema;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Position(u32);
+
+schema! {
+    position(at: Position): u64;
+}
+
+fn main() {}
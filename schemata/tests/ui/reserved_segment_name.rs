@@ -0,0 +1,14 @@
+use schemata::schema;
+
+schema! {
+    account(id: u32) {
+        params: u64;
+        #[rename = "parent"]
+        owner: u64;
+    }
+    to_owned {
+        count: u64;
+    }
+}
+
+fn main() {}
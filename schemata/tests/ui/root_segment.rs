@@ -0,0 +1,9 @@
+use schemata::schema;
+
+schema! {
+    root {
+        count: u64;
+    }
+}
+
+fn main() {}
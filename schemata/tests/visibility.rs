@@ -0,0 +1,68 @@
+use schemata::{Decode, Encode};
+
+mod store {
+    use schemata::{schema, Encode};
+
+    schema! {
+        #![schemata(visibility = "pub(crate)")]
+
+        height: u64;
+        account(id: u32) {
+            balance: u64;
+            /// Only visible within `store`.
+            pub(super) nonce: u64;
+        }
+        pub(self) internal {
+            counter: u64;
+        }
+    }
+
+    pub fn counter_key() -> Vec<u8> {
+        Schema::root().internal().counter().key().to_bytes()
+    }
+
+    pub fn nonce_key(id: u32) -> Vec<u8> {
+        Schema::root().account(id).nonce().key().to_bytes()
+    }
+}
+
+#[test]
+fn nested_types_are_nameable() {
+    let key: store::account::id::balance::Key = store::Schema::root().account(7).balance().key();
+    assert_eq!(key.to_bytes(), b"account/\0\0\0\x07/balance");
+
+    let owned: store::account::id::balance::OwnedKey =
+        Decode::from_bytes(b"account/\0\0\0\x07/balance").unwrap();
+    assert_eq!(owned.to_bytes(), key.to_bytes());
+    assert_eq!(store::height().key().to_bytes(), b"height");
+}
+
+#[test]
+fn match_on_decoded_key() {
+    let key = store::OwnedKey::from_bytes(b"account/\0\0\0\x07/balance").unwrap();
+    let store::OwnedSubKey::account(account) = key.child() else {
+        panic!("expected an account key");
+    };
+    let store::account::OwnedSubKey::id(id) = account.child();
+    assert_eq!(id.params().id, 7);
+    assert!(matches!(
+        id.child(),
+        store::account::id::OwnedSubKey::balance(_)
+    ));
+
+    let leaf = store::account::id::balance::OwnedKey::from_bytes(&key.to_bytes()).unwrap();
+    assert!(leaf.root() == &key);
+    assert!(leaf.into_root() == key);
+}
+
+#[test]
+fn restricted_segments() {
+    assert_eq!(store::counter_key(), b"internal/counter");
+    assert_eq!(store::nonce_key(1), b"account/\0\0\0\x01/nonce");
+
+    let prefix = store::Schema::root().account(1).prefix();
+    let store::SubPrefix::account(account) = prefix.child().unwrap() else {
+        panic!("expected an account prefix");
+    };
+    assert!(account.child().is_some());
+}